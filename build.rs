@@ -10,6 +10,18 @@ fn main() {
     add_res("SIMPLE_SHADER", "src/simple_shader.wgsl");
     add_res("SQUARE_SHADER", "src/square_shader.wgsl");
     add_res("TEXTURE_SHADER", "src/texture_shader.wgsl");
+    add_res("POSTPROCESS_SHADER", "src/postprocess_shader.wgsl");
+    add_res("POSTPROCESS_COPY_SHADER", "src/postprocess_copy.wgsl");
+    add_res("POSTPROCESS_GRADE_SHADER", "src/postprocess_grade.wgsl");
+    add_res("POSTPROCESS_VIGNETTE_SHADER", "src/postprocess_vignette.wgsl");
+    add_res("POSTPROCESS_BLOOM_SHADER", "src/postprocess_bloom.wgsl");
+    add_res("POSTPROCESS_CRT_SHADER", "src/postprocess_crt.wgsl");
+    add_res("POSTPROCESS_BLUR_SHADER", "src/postprocess_blur.wgsl");
+    add_res("PIXEL_SHADER", "src/pixel_shader.wgsl");
+    add_res("FONT_SHADER", "src/font_shader.wgsl");
+    add_res("FONT_SDF_SHADER", "src/font_sdf_shader.wgsl");
     add_res("SAMPLE_IMAGE", "res/image.png");
+    add_res("SAMPLE_FONT", "res/font.ttf");
+    add_res("SAMPLE_FALLBACK_FONT", "res/fallback.ttf");
 }
 
@@ -0,0 +1,299 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use winit::{
+    event::{ElementState, KeyEvent, MouseButton, WindowEvent},
+    keyboard::{Key, KeyCode, PhysicalKey},
+};
+
+use crate::win::EventState;
+
+// Named actions on top of raw input. Buttons ("jump") are pressed, held or
+// released, axes ("move_x") have a value between -1 and 1.
+//
+// Keyboard and mouse input comes from Client::handle_event. winit has no
+// gamepad support, so gamepad state is fed in with `gamepad_button` and
+// `gamepad_axis` from whichever gamepad library the app uses.
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Button {
+    // layout dependent, character keys are matched case insensitively
+    Key(Key),
+    // layout independent key position
+    Physical(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AxisBinding {
+    // -1 while `negative` is held, 1 while `positive` is held
+    Buttons { negative: Button, positive: Button },
+    // values inside the dead zone read as 0
+    Gamepad { axis: GamepadAxis, dead_zone: f32, invert: bool },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Bindings {
+    pub buttons: BTreeMap<String, Vec<Button>>,
+    pub axes: BTreeMap<String, Vec<AxisBinding>>,
+}
+
+#[derive(Debug)]
+pub enum BindingsError {
+    Io(std::io::Error),
+    Format(serde_json::Error),
+}
+
+pub struct ActionMap {
+    // change through set_bindings or rebind while buttons may be held
+    pub bindings: Bindings,
+    down: HashSet<Button>,
+    pos_edge: HashSet<Button>,
+    neg_edge: HashSet<Button>,
+    gamepad_axes: HashMap<GamepadAxis, f32>,
+}
+
+impl Bindings {
+    pub fn new() -> Bindings {
+        Default::default()
+    }
+
+    pub fn bind(&mut self, action: &str, button: Button) {
+        self.buttons.entry(action.to_string()).or_default().push(normalize(button));
+    }
+
+    pub fn bind_axis(&mut self, action: &str, binding: AxisBinding) {
+        let binding = match binding {
+            AxisBinding::Buttons { negative, positive } => AxisBinding::Buttons {
+                negative: normalize(negative),
+                positive: normalize(positive),
+            },
+            binding => binding,
+        };
+        self.axes.entry(action.to_string()).or_default().push(binding);
+    }
+
+    pub fn unbind(&mut self, action: &str) {
+        self.buttons.remove(action);
+        self.axes.remove(action);
+    }
+
+    pub fn from_json(json: &str) -> Result<Bindings, BindingsError> {
+        let bindings: Bindings = serde_json::from_str(json).map_err(BindingsError::Format)?;
+        let mut normalized = Bindings::new();
+        for (action, buttons) in bindings.buttons {
+            buttons.into_iter().for_each(|button| normalized.bind(&action, button));
+        }
+        for (action, axes) in bindings.axes {
+            axes.into_iter().for_each(|axis| normalized.bind_axis(&action, axis));
+        }
+        Ok(normalized)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Bindings always serialize")
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Bindings, BindingsError> {
+        let json = std::fs::read_to_string(path).map_err(BindingsError::Io)?;
+        Self::from_json(&json)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), BindingsError> {
+        std::fs::write(path, self.to_json()).map_err(BindingsError::Io)
+    }
+}
+
+// Shift turns "a" into "A", bindings shouldn't care
+fn normalize(button: Button) -> Button {
+    match button {
+        Button::Key(Key::Character(c)) => Button::Key(Key::Character(c.to_lowercase().into())),
+        button => button,
+    }
+}
+
+impl ActionMap {
+    pub fn new(bindings: Bindings) -> ActionMap {
+        ActionMap {
+            bindings,
+            down: HashSet::new(),
+            pos_edge: HashSet::new(),
+            neg_edge: HashSet::new(),
+            gamepad_axes: HashMap::new(),
+        }
+    }
+
+    // Replaces every binding. Buttons that are no longer bound stop being
+    // held, their release wouldn't reach this map any more.
+    pub fn set_bindings(&mut self, bindings: Bindings) {
+        self.bindings = bindings;
+        self.forget_unbound();
+    }
+
+    // Binds `action` to `buttons` instead of what it had
+    pub fn rebind(&mut self, action: &str, buttons: Vec<Button>) {
+        self.bindings.buttons.remove(action);
+        buttons.into_iter().for_each(|button| self.bindings.bind(action, button));
+        self.forget_unbound();
+    }
+
+    fn forget_unbound(&mut self) {
+        let unbound: Vec<Button> = self.down.iter()
+            .chain(&self.pos_edge)
+            .filter(|button| !self.is_bound(button))
+            .cloned()
+            .collect();
+        for button in unbound {
+            self.down.remove(&button);
+            self.pos_edge.remove(&button);
+        }
+    }
+
+    // Consumes the event when it changed a bound button
+    pub fn handle_event(&mut self, event: &WindowEvent) -> EventState {
+        let bound = match event {
+            WindowEvent::KeyboardInput {
+                event: KeyEvent {
+                    physical_key,
+                    logical_key,
+                    state,
+                    repeat,
+                    ..
+                },
+                ..
+            } => {
+                let logical = self.key(logical_key, *state, *repeat);
+                let physical = match physical_key {
+                    PhysicalKey::Code(code) => self.button(Button::Physical(*code), *state, *repeat),
+                    PhysicalKey::Unidentified(_) => false,
+                };
+                logical || physical
+            },
+            WindowEvent::MouseInput { state, button, .. } => self.button(Button::Mouse(*button), *state, false),
+            WindowEvent::Focused(false) => {
+                self.neg_edge.extend(self.down.drain());
+                false
+            },
+            _ => false,
+        };
+        if bound { EventState::Consumed } else { EventState::Skipped }
+    }
+
+    // Returns whether the key is bound to anything
+    pub fn key(&mut self, key: &Key, state: ElementState, repeat: bool) -> bool {
+        self.button(normalize(Button::Key(key.clone())), state, repeat)
+    }
+
+    pub fn gamepad_button(&mut self, button: GamepadButton, state: ElementState) -> bool {
+        self.button(Button::Gamepad(button), state, false)
+    }
+
+    pub fn gamepad_axis(&mut self, axis: GamepadAxis, value: f32) {
+        self.gamepad_axes.insert(axis, value.clamp(-1.0, 1.0));
+    }
+
+    fn button(&mut self, button: Button, state: ElementState, repeat: bool) -> bool {
+        if !self.is_bound(&button) {
+            return false;
+        }
+        match state {
+            ElementState::Pressed => {
+                if !repeat && self.down.insert(button.clone()) {
+                    self.pos_edge.insert(button);
+                }
+            },
+            ElementState::Released => {
+                if self.down.remove(&button) {
+                    self.neg_edge.insert(button);
+                }
+            },
+        }
+        true
+    }
+
+    fn is_bound(&self, button: &Button) -> bool {
+        self.bindings.buttons.values().flatten().any(|bound| bound == button)
+            || self.bindings.axes.values().flatten().any(|axis| match axis {
+                AxisBinding::Buttons { negative, positive } => negative == button || positive == button,
+                AxisBinding::Gamepad { .. } => false,
+            })
+    }
+
+    fn any(&self, action: &str, set: &HashSet<Button>) -> bool {
+        self.bindings.buttons.get(action)
+            .is_some_and(|buttons| buttons.iter().any(|button| set.contains(button)))
+    }
+
+    pub fn down(&self, action: &str) -> bool {
+        self.any(action, &self.down)
+    }
+
+    // Pressed since the last end_frame
+    pub fn pressed(&self, action: &str) -> bool {
+        self.any(action, &self.pos_edge)
+    }
+
+    // Released since the last end_frame
+    pub fn released(&self, action: &str) -> bool {
+        self.any(action, &self.neg_edge)
+    }
+
+    // The binding furthest from 0 wins
+    pub fn axis(&self, action: &str) -> f32 {
+        let Some(bindings) = self.bindings.axes.get(action) else { return 0.0 };
+        bindings.iter()
+            .map(|binding| match binding {
+                AxisBinding::Buttons { negative, positive } => {
+                    let negative = if self.down.contains(negative) { -1.0 } else { 0.0 };
+                    let positive = if self.down.contains(positive) { 1.0 } else { 0.0 };
+                    negative + positive
+                },
+                AxisBinding::Gamepad { axis, dead_zone, invert } => {
+                    let value = self.gamepad_axes.get(axis).copied().unwrap_or(0.0);
+                    let value = if value.abs() < *dead_zone { 0.0 } else { value };
+                    if *invert { -value } else { value }
+                },
+            })
+            .fold(0.0, |best: f32, value| if value.abs() > best.abs() { value } else { best })
+    }
+
+    // Clears pressed and released edges, call once per frame after reading them
+    pub fn end_frame(&mut self) {
+        self.pos_edge.clear();
+        self.neg_edge.clear();
+    }
+}
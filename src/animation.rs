@@ -0,0 +1,283 @@
+use std::collections::BTreeMap;
+
+use winit::dpi::PhysicalSize;
+
+use crate::{
+    geom::Rect,
+    mat::vec2,
+    texture::TextureInfo,
+};
+
+// The frames of a texture, as rects in texels from its top left. Frame
+// indices are what clips refer to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteSheet {
+    pub size: PhysicalSize<u32>,
+    pub frames: Vec<Rect>,
+}
+
+impl SpriteSheet {
+    pub fn new(size: PhysicalSize<u32>, frames: Vec<Rect>) -> SpriteSheet {
+        SpriteSheet { size, frames }
+    }
+
+    // Slices `size` into `cell` sized frames, left to right then top to
+    // bottom. Partial cells at the right and bottom edges are left out.
+    pub fn grid(size: PhysicalSize<u32>, cell: PhysicalSize<u32>) -> SpriteSheet {
+        Self::grid_spaced(size, cell, 0, 0)
+    }
+
+    // Like grid for sheets with `margin` texels around the edge and
+    // `spacing` between cells
+    pub fn grid_spaced(size: PhysicalSize<u32>, cell: PhysicalSize<u32>, margin: u32, spacing: u32) -> SpriteSheet {
+        assert!(cell.width > 0 && cell.height > 0, "Sprite sheet cells can't be empty");
+        let count = |length: u32, cell: u32| (length.saturating_sub(margin * 2) + spacing) / (cell + spacing);
+        let (columns, rows) = (count(size.width, cell.width), count(size.height, cell.height));
+        let frames = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let x = margin + column * (cell.width + spacing);
+                let y = margin + row * (cell.height + spacing);
+                Rect::from_pos_size(vec2(x as f32, y as f32), vec2(cell.width as f32, cell.height as f32))
+            })
+            .collect();
+        SpriteSheet { size, frames }
+    }
+
+    pub fn from_texture(texture: &TextureInfo, cell: PhysicalSize<u32>) -> SpriteSheet {
+        let size = texture.texture.size();
+        Self::grid(PhysicalSize::new(size.width, size.height), cell)
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    // The frame as uvs for textured::RenderParams
+    pub fn uv(&self, frame: usize) -> Option<Rect> {
+        let size = vec2(self.size.width.max(1) as f32, self.size.height.max(1) as f32);
+        self.frames.get(frame).map(|rect| Rect::new(rect.min / size, rect.max / size))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Playback {
+    // back to the first frame after the last
+    Loop,
+    // back and forth, without repeating the end frames
+    PingPong,
+    // stops on the last frame
+    Once,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    // into the sprite sheet
+    pub index: usize,
+    // in seconds
+    pub duration: f32,
+}
+
+// A named sequence of frames. Events are names attached to positions in
+// the sequence and fire whenever playback enters them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clip {
+    pub frames: Vec<Frame>,
+    pub playback: Playback,
+    pub events: Vec<(usize, String)>,
+}
+
+impl Clip {
+    pub fn new(playback: Playback) -> Clip {
+        Clip { frames: Vec::new(), playback, events: Vec::new() }
+    }
+
+    pub fn frame(mut self, index: usize, duration: f32) -> Clip {
+        assert!(duration > 0.0, "Frame durations must be positive");
+        self.frames.push(Frame { index, duration });
+        self
+    }
+
+    // Several sheet frames in a row with the same duration
+    pub fn frames(self, indices: impl IntoIterator<Item = usize>, duration: f32) -> Clip {
+        indices.into_iter().fold(self, |clip, index| clip.frame(index, duration))
+    }
+
+    // `position` is in the clip's sequence, not the sheet
+    pub fn event(mut self, position: usize, name: &str) -> Clip {
+        self.events.push((position, name.to_string()));
+        self
+    }
+
+    // One pass over the frames, the same both ways for PingPong
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+
+    fn events_at(&self, position: usize) -> impl Iterator<Item = &str> {
+        self.events.iter()
+            .filter(move |(at, _)| *at == position)
+            .map(|(_, name)| name.as_str())
+    }
+}
+
+// A sprite sheet and the clips that play over it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Animations {
+    pub sheet: SpriteSheet,
+    pub clips: BTreeMap<String, Clip>,
+}
+
+impl Animations {
+    pub fn new(sheet: SpriteSheet) -> Animations {
+        Animations { sheet, clips: BTreeMap::new() }
+    }
+
+    pub fn add(&mut self, name: &str, clip: Clip) {
+        assert!(clip.frames.iter().all(|frame| frame.index < self.sheet.len()), "Clip {} is out of the sprite sheet", name);
+        assert!(clip.frames.iter().all(|frame| frame.duration > 0.0), "Clip {} has frames without a positive duration", name);
+        self.clips.insert(name.to_string(), clip);
+    }
+
+    pub fn clip(&self, name: &str) -> Option<&Clip> {
+        self.clips.get(name)
+    }
+}
+
+// Where one thing is in its animation. Players only hold the clip's name,
+// so any number of them can share an Animations, and nothing but `update`
+// moves them forward, which keeps them deterministic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Player {
+    clip: Option<String>,
+    // in the clip's sequence
+    position: usize,
+    // time spent on the current frame
+    elapsed: f32,
+    backwards: bool,
+    finished: bool,
+    // the current frame's events haven't been reported yet
+    entered: bool,
+    // multiplies the time passed to update
+    pub speed: f32,
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Player {
+            clip: None,
+            position: 0,
+            elapsed: 0.0,
+            backwards: false,
+            finished: false,
+            entered: false,
+            speed: 1.0,
+        }
+    }
+}
+
+impl Player {
+    pub fn new() -> Player {
+        Self::default()
+    }
+
+    // Switches to `clip` from its start. Playing the clip that's already
+    // playing carries on, use restart to start it over.
+    pub fn play(&mut self, clip: &str) {
+        if self.clip.as_deref() != Some(clip) {
+            self.restart(clip);
+        }
+    }
+
+    pub fn restart(&mut self, clip: &str) {
+        *self = Player { clip: Some(clip.to_string()), entered: true, speed: self.speed, ..Default::default() };
+    }
+
+    pub fn clip(&self) -> Option<&str> {
+        self.clip.as_deref()
+    }
+
+    // Where playback is in the clip's sequence
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    // Once clips that have reached the end of their last frame
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // Moves on by `dt` seconds and returns the events of the frames that
+    // were entered, in order. A clip's first frame counts as entered by
+    // the first update after it starts playing. Each frame reports its
+    // events at most once per update, however many loops `dt` covers.
+    pub fn update<'a>(&mut self, animations: &'a Animations, dt: f32) -> Vec<&'a str> {
+        let mut events = Vec::new();
+        let Some(clip) = self.clip.as_deref().and_then(|name| animations.clip(name)) else { return events };
+        if clip.frames.is_empty() {
+            return events;
+        }
+        if self.entered {
+            self.entered = false;
+            events.extend(clip.events_at(self.position));
+        }
+        if self.finished {
+            return events;
+        }
+        self.elapsed += dt * self.speed;
+        // whole loops end on the frame they started from, so they're
+        // skipped after every frame has reported entering
+        let looped = clip.playback == Playback::Loop && self.elapsed >= clip.duration();
+        if looped {
+            self.elapsed %= clip.duration();
+            let count = clip.frames.len();
+            (1..=count).for_each(|offset| events.extend(clip.events_at((self.position + offset) % count)));
+        }
+        while self.elapsed >= clip.frames[self.position].duration {
+            let Some(next) = self.next_position(clip) else {
+                self.elapsed = clip.frames[self.position].duration;
+                self.finished = true;
+                break;
+            };
+            self.elapsed -= clip.frames[self.position].duration;
+            self.position = next;
+            if !looped {
+                events.extend(clip.events_at(self.position));
+            }
+        }
+        events
+    }
+
+    fn next_position(&mut self, clip: &Clip) -> Option<usize> {
+        let last = clip.frames.len() - 1;
+        match clip.playback {
+            Playback::Loop => Some(if self.position == last { 0 } else { self.position + 1 }),
+            Playback::Once => (self.position < last).then_some(self.position + 1),
+            // a single frame has nowhere to go but itself
+            Playback::PingPong if last == 0 => Some(0),
+            Playback::PingPong => {
+                if self.position == last {
+                    self.backwards = true;
+                } else if self.position == 0 {
+                    self.backwards = false;
+                }
+                Some(if self.backwards { self.position - 1 } else { self.position + 1 })
+            },
+        }
+    }
+
+    // The sheet frame to show, None without a clip
+    pub fn frame(&self, animations: &Animations) -> Option<usize> {
+        let clip = animations.clip(self.clip.as_deref()?)?;
+        clip.frames.get(self.position).map(|frame| frame.index)
+    }
+
+    // The current frame's uvs for textured::RenderParams
+    pub fn uv(&self, animations: &Animations) -> Option<Rect> {
+        animations.sheet.uv(self.frame(animations)?)
+    }
+}
@@ -0,0 +1,352 @@
+use std::{collections::{BTreeMap, HashMap}, fmt};
+
+use serde::{de::{self, MapAccess, SeqAccess, Visitor}, Deserialize, Deserializer};
+use winit::dpi::PhysicalSize;
+
+use crate::{
+    animation::{Animations, Clip, Playback, SpriteSheet},
+    geom::Rect,
+    mat::{vec2, Mat4, Vec2},
+};
+
+// A named part of an atlas texture. Packers trim the transparent edges off
+// sprites, `offset` and `source_size` say where the trimmed rect sat in the
+// sprite it was cut from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+    pub name: String,
+    // in texels from the texture's top left
+    pub rect: Rect,
+    // packed turned 90° clockwise, so `rect` and its uvs are sideways
+    pub rotated: bool,
+    pub trimmed: bool,
+    pub offset: Vec2,
+    pub source_size: Vec2,
+    // the sprite's origin as a fraction of source_size, the center unless
+    // the exporter says otherwise
+    pub pivot: Vec2,
+    // Aseprite frames have one, in seconds
+    pub duration: Option<f32>,
+}
+
+// An Aseprite slice, a named rect that can change from frame to frame.
+// Keys hold from their frame until the next key's.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Slice {
+    pub name: String,
+    pub keys: Vec<SliceKey>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SliceKey {
+    pub frame: usize,
+    // in the sprite's texels
+    pub bounds: Rect,
+    // nine-slice center and pivot, relative to the bounds' top left
+    pub center: Option<Rect>,
+    pub pivot: Option<Vec2>,
+}
+
+// What an Aseprite or TexturePacker json sidecar describes. Regions are in
+// the exported order, and index the sprite sheet from `sheet`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Atlas {
+    pub size: PhysicalSize<u32>,
+    pub regions: Vec<Region>,
+    pub clips: BTreeMap<String, Clip>,
+    pub slices: Vec<Slice>,
+}
+
+#[derive(Debug)]
+pub enum AtlasError {
+    Format(serde_json::Error),
+    // well formed json that doesn't make sense, like a tag past the last frame
+    Invalid(String),
+}
+
+impl Region {
+    // The trimmed sprite's size the right way up
+    pub fn size(&self) -> Vec2 {
+        let size = self.rect.size();
+        if self.rotated { vec2(size.y, size.x) } else { size }
+    }
+
+    // Where to draw the region with the textured renderer so the pivot
+    // lands on `position`, at `scale` world units per texel. Trimmed
+    // regions stay where they were in the untrimmed sprite, and rotated
+    // ones are turned back upright.
+    pub fn matrix(&self, position: Vec2, scale: f32) -> Mat4 {
+        let center = self.offset + self.size() / 2.0 - self.pivot * self.source_size;
+        let rotation = if self.rotated { std::f32::consts::FRAC_PI_2 } else { 0.0 };
+        Mat4::box2d_rot(position + center * scale, self.rect.size() * scale, rotation)
+    }
+}
+
+impl Slice {
+    // The key in effect on `frame`
+    pub fn key(&self, frame: usize) -> Option<&SliceKey> {
+        self.keys.iter().rev().find(|key| key.frame <= frame)
+    }
+}
+
+impl Atlas {
+    // Reads Aseprite's "Export Sprite Sheet" json, with frames as a hash or
+    // an array. Tags become clips with the frames' durations, slices are
+    // kept as they are.
+    pub fn from_aseprite(json: &str) -> Result<Atlas, AtlasError> {
+        let file: JsonAtlas = serde_json::from_str(json).map_err(AtlasError::Format)?;
+        let mut atlas = Self::from_json(&file);
+        for tag in &file.meta.frame_tags {
+            if tag.from > tag.to || tag.to >= atlas.regions.len() {
+                return Err(AtlasError::Invalid(format!("Tag {} is out of the frames", tag.name)));
+            }
+            let (playback, reverse) = match tag.direction.as_str() {
+                "pingpong" => (Playback::PingPong, false),
+                "pingpong_reverse" => (Playback::PingPong, true),
+                direction => {
+                    // Aseprite only writes repeat when it's limited
+                    let playback = if tag.repeat.as_deref() == Some("1") { Playback::Once } else { Playback::Loop };
+                    (playback, direction == "reverse")
+                },
+            };
+            let mut indices: Vec<usize> = (tag.from..=tag.to).collect();
+            if reverse {
+                indices.reverse();
+            }
+            let mut clip = Clip::new(playback);
+            for index in indices {
+                let region = &atlas.regions[index];
+                let duration = region.duration
+                    .filter(|duration| *duration > 0.0)
+                    .ok_or_else(|| AtlasError::Invalid(format!("Frame {} has no duration", region.name)))?;
+                clip = clip.frame(index, duration);
+            }
+            atlas.clips.insert(tag.name.clone(), clip);
+        }
+        atlas.slices = file.meta.slices.iter()
+            .map(|slice| Slice {
+                name: slice.name.clone(),
+                keys: slice.keys.iter()
+                    .map(|key| SliceKey {
+                        frame: key.frame,
+                        bounds: key.bounds.rect(),
+                        center: key.center.as_ref().map(JsonRect::rect),
+                        pivot: key.pivot.as_ref().map(|pivot| vec2(pivot.x, pivot.y)),
+                    })
+                    .collect(),
+            })
+            .collect();
+        Ok(atlas)
+    }
+
+    // Reads TexturePacker's json, hash or array, including the animations
+    // list of the PixiJS flavor. TexturePacker doesn't store timing, so
+    // every animation frame lasts `frame_duration` seconds and loops.
+    // Frames packed sideways keep their texel rect with `rotated` set, and
+    // `frame_duration` has to be positive.
+    pub fn from_texture_packer(json: &str, frame_duration: f32) -> Result<Atlas, AtlasError> {
+        if frame_duration <= 0.0 || frame_duration.is_nan() {
+            return Err(AtlasError::Invalid(format!("Frame duration {} isn't positive", frame_duration)));
+        }
+        let file: JsonAtlas = serde_json::from_str(json).map_err(AtlasError::Format)?;
+        let mut atlas = Self::from_json(&file);
+        let indices: HashMap<&str, usize> = atlas.regions.iter()
+            .enumerate()
+            .map(|(index, region)| (region.name.as_str(), index))
+            .collect();
+        for (name, frames) in &file.animations {
+            let mut clip = Clip::new(Playback::Loop);
+            for frame in frames {
+                let index = indices.get(frame.as_str())
+                    .ok_or_else(|| AtlasError::Invalid(format!("Animation {} uses missing frame {}", name, frame)))?;
+                clip = clip.frame(*index, frame_duration);
+            }
+            atlas.clips.insert(name.clone(), clip);
+        }
+        Ok(atlas)
+    }
+
+    // the parts both exporters share
+    fn from_json(file: &JsonAtlas) -> Atlas {
+        let regions = file.frames.0.iter()
+            .map(|(name, frame)| {
+                // rotated frames give their size the right way up
+                let upright = frame.frame.rect();
+                let rect = match frame.rotated {
+                    true => Rect::from_pos_size(upright.min, vec2(upright.height(), upright.width())),
+                    false => upright,
+                };
+                let trim = frame.sprite_source_size.as_ref().map_or(upright.translate(-upright.min), JsonRect::rect);
+                let source_size = frame.source_size.as_ref().map_or(upright.size(), |size| vec2(size.w as f32, size.h as f32));
+                Region {
+                    name: name.clone(),
+                    rect,
+                    rotated: frame.rotated,
+                    trimmed: frame.trimmed,
+                    offset: trim.min,
+                    source_size,
+                    pivot: frame.pivot.as_ref().map_or(vec2(0.5, 0.5), |pivot| vec2(pivot.x, pivot.y)),
+                    duration: frame.duration.map(|ms| ms / 1000.0),
+                }
+            })
+            .collect();
+        Atlas {
+            size: PhysicalSize::new(file.meta.size.w, file.meta.size.h),
+            regions,
+            clips: BTreeMap::new(),
+            slices: Vec::new(),
+        }
+    }
+
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.regions.iter().position(|region| region.name == name)
+    }
+
+    pub fn region(&self, name: &str) -> Option<&Region> {
+        self.regions.iter().find(|region| region.name == name)
+    }
+
+    pub fn slice(&self, name: &str) -> Option<&Slice> {
+        self.slices.iter().find(|slice| slice.name == name)
+    }
+
+    // A region's uvs for textured::RenderParams
+    pub fn uv(&self, name: &str) -> Option<Rect> {
+        self.sheet().uv(self.index(name)?)
+    }
+
+    // The regions as sprite sheet frames, in order
+    pub fn sheet(&self) -> SpriteSheet {
+        SpriteSheet::new(self.size, self.regions.iter().map(|region| region.rect).collect())
+    }
+
+    pub fn animations(&self) -> Animations {
+        let mut animations = Animations::new(self.sheet());
+        for (name, clip) in &self.clips {
+            animations.add(name, clip.clone());
+        }
+        animations
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonAtlas {
+    frames: JsonFrames,
+    meta: JsonMeta,
+    // TexturePacker's PixiJS format
+    #[serde(default)]
+    animations: BTreeMap<String, Vec<String>>,
+}
+
+// in the order they're written, which a map would lose
+struct JsonFrames(Vec<(String, JsonFrame)>);
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonFrame {
+    // only in the array format
+    filename: Option<String>,
+    frame: JsonRect,
+    #[serde(default)]
+    rotated: bool,
+    #[serde(default)]
+    trimmed: bool,
+    sprite_source_size: Option<JsonRect>,
+    source_size: Option<JsonSize>,
+    pivot: Option<JsonPoint>,
+    // in milliseconds
+    duration: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonMeta {
+    size: JsonSize,
+    #[serde(default)]
+    frame_tags: Vec<JsonTag>,
+    #[serde(default)]
+    slices: Vec<JsonSlice>,
+}
+
+#[derive(Deserialize)]
+struct JsonTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: String,
+    repeat: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonSlice {
+    name: String,
+    keys: Vec<JsonSliceKey>,
+}
+
+#[derive(Deserialize)]
+struct JsonSliceKey {
+    frame: usize,
+    bounds: JsonRect,
+    center: Option<JsonRect>,
+    pivot: Option<JsonPoint>,
+}
+
+#[derive(Deserialize)]
+struct JsonRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct JsonSize {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct JsonPoint {
+    x: f32,
+    y: f32,
+}
+
+impl JsonRect {
+    fn rect(&self) -> Rect {
+        Rect::from_pos_size(vec2(self.x as f32, self.y as f32), vec2(self.w as f32, self.h as f32))
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonFrames {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FramesVisitor;
+
+        impl<'de> Visitor<'de> for FramesVisitor {
+            type Value = JsonFrames;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("frames by name or a list of frames with filenames")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonFrames, A::Error> {
+                let mut frames = Vec::new();
+                while let Some(entry) = map.next_entry::<String, JsonFrame>()? {
+                    frames.push(entry);
+                }
+                Ok(JsonFrames(frames))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonFrames, A::Error> {
+                let mut frames = Vec::new();
+                while let Some(frame) = seq.next_element::<JsonFrame>()? {
+                    let name = frame.filename.clone().ok_or_else(|| de::Error::missing_field("filename"))?;
+                    frames.push((name, frame));
+                }
+                Ok(JsonFrames(frames))
+            }
+        }
+
+        deserializer.deserialize_any(FramesVisitor)
+    }
+}
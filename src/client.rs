@@ -1,14 +1,30 @@
 use crate::{
-    mat::{vec2, Mat4}, rrs::{RenderConstruct, Record, RenderRecordSystem, Settings}, simple, square, texture::init_texture, textured, util::indirect_handles::WeakHandle, win
+    animation::{Animations, Clip, Playback, Player, SpriteSheet}, console::{CommandRegistry, Console, ReceiveResult}, dispatch::{EventDispatcher, Response}, frame::{Attachment, FrameGraph, Pass}, geom::Rect, mat::{vec2, vec4, Mat4}, pixel::PixelPerfect, postprocess::{self, PostProcess}, projection::{Policy, Projection}, rrs::{FrameStats, RenderConstruct, Record, RenderRecordSystem, Settings}, simple, square, target::RenderTarget, text::{self, BaseFontInfoContainer}, texture::init_texture, textured, util::{indirect_handles::WeakHandle, ScaleVariants}, win
 };
 
 pub fn run() {
-    win::run(Client::init);
+    win::run(win::WindowConfig::default(), Client::init);
 }
 
 pub mod render {
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layer {
+    Console,
+    World,
+}
+
+// state the console commands can change
+struct DevSettings {
+    show_stats: bool,
+    vignette: bool,
+    cull: bool,
+    pixel_perfect: bool,
+    // false when PixelPerfect couldn't be made, which keeps pixel_perfect off
+    pixel_supported: bool,
+}
+
 pub struct Client {
     ortho: Mat4,
     rrs: RenderRecordSystem,
@@ -16,6 +32,24 @@ pub struct Client {
     square_render: square::Construct,
     textured_render: textured::Construct,
     texture: WeakHandle<textured::Texture>,
+    // the sample image cut into quarters, played in a loop
+    animations: Animations,
+    player: Player,
+    graph: FrameGraph,
+    minimap_texture: WeakHandle<textured::Texture>,
+    post: PostProcess,
+    vignette: usize,
+    pixel: Option<PixelPerfect>,
+    events: EventDispatcher<Layer>,
+    text_render: text::Construct,
+    // the console font for each scale factor
+    fonts: ScaleVariants<WeakHandle<text::Font>>,
+    sdf_font: WeakHandle<text::Font>,
+    console: Console,
+    commands: CommandRegistry<DevSettings>,
+    dev: DevSettings,
+    // from the last frame, for the stats overlay
+    stats: FrameStats,
 }
 
 impl Client {
@@ -24,9 +58,76 @@ impl Client {
         let simple_render = rrs.add(simple::Construct::init(rc));
         let square_render = rrs.add(square::Construct::init(rc));
         let mut textured_render = rrs.add(textured::Construct::init(rc));
+        let mut text_render = rrs.add(text::Construct::init(rc));
         let texture_base = init_texture(rc, include_bytes!(env!("SAMPLE_IMAGE")), wgpu::FilterMode::Nearest)
             .expect("Could not load texture");
         let texture = textured_render.init_texture(rc, &mut rrs, &texture_base).make_weak();
+        let image_size = texture_base.texture.size();
+        let cell = winit::dpi::PhysicalSize::new(image_size.width / 2, image_size.height / 2);
+        let mut animations = Animations::new(SpriteSheet::from_texture(&texture_base, cell));
+        animations.add("quarters", Clip::new(Playback::PingPong).frames([0, 1, 3, 2], 0.25));
+        let mut player = Player::new();
+        player.play("quarters");
+        let minimap = RenderTarget::init(rc, winit::dpi::PhysicalSize::new(320, 180));
+        let minimap_texture = textured_render.init_texture(rc, &mut rrs, &minimap.info).make_weak();
+        let mut graph = FrameGraph::new();
+        graph.clear_color = wgpu::Color { r: 0.1, g: 0.2, b: 0.3, a: 1.0 };
+        graph.add_target("minimap", minimap);
+        graph.add_pass(Pass::new("minimap", Attachment::Target("minimap")).clear(wgpu::Color::BLACK));
+        graph.add_pass(Pass::new("pixel", Attachment::External).clear(wgpu::Color::BLACK).after("minimap"));
+        graph.add_pass(Pass::new("scene", Attachment::External).after("pixel"));
+        graph.add_pass(Pass::new("post", Attachment::Surface).after("scene"));
+        graph.validate().expect("Invalid frame graph");
+        let mut post = PostProcess::init(rc, rc.window.inner_size());
+        let vignette = post.vignette(rc, postprocess::Vignette::default());
+        let vignette = post.add(vignette);
+        // needs MSAA off, otherwise the pixel command stays unavailable
+        let pixel = PixelPerfect::init(rc, winit::dpi::PhysicalSize::new(320, 180));
+        let pixel_supported = pixel.is_some();
+        let mut events = EventDispatcher::new();
+        events.register(Layer::World, 0);
+        events.register(Layer::Console, 10);
+
+        // the console lays out with the 16 pixel metrics and draws the 32
+        // pixel version at half size on high DPI screens
+        let font_infos = text::make_font_infos(
+            include_bytes!(env!("SAMPLE_FONT")), &[16.0, 32.0], text::default_characters().iter(), None, "sample".to_string())
+            .expect("Could not load font");
+        let mut fonts = ScaleVariants::new();
+        for (scale, font_info) in [1.0, 2.0].into_iter().zip(&font_infos) {
+            fonts.add(scale, text_render.init_font(rc, &mut rrs, font_info, wgpu::FilterMode::Nearest).make_weak());
+        }
+        let font_info = &font_infos[0];
+        let sdf_font_info = text::make_sdf_font_info(
+            include_bytes!(env!("SAMPLE_FONT")), 32.0, text::default_characters().iter(), None, 6, "sample_sdf".to_string())
+            .expect("Could not load font");
+        let sdf_font = text_render.init_font(rc, &mut rrs, &sdf_font_info, wgpu::FilterMode::Linear).make_weak();
+        let mut console = Console::new(font_info.get_metrics_info(), font_info.line_height(), 200);
+        console.resize(rc.logical_size().width, 10);
+        console.println("Press Enter or ` for the console, type help for commands");
+        let mut commands = CommandRegistry::new();
+        commands.register("stats", "toggles the frame time overlay", |dev: &mut DevSettings, _: &[&str]| {
+            dev.show_stats = !dev.show_stats;
+            Ok(format!("stats {}", if dev.show_stats { "on" } else { "off" }))
+        });
+        commands.register("vignette", "toggles the vignette effect", |dev: &mut DevSettings, _: &[&str]| {
+            dev.vignette = !dev.vignette;
+            Ok(format!("vignette {}", if dev.vignette { "on" } else { "off" }))
+        });
+        commands.register("cull", "toggles culling of off screen entries", |dev: &mut DevSettings, _: &[&str]| {
+            dev.cull = !dev.cull;
+            Ok(format!("cull {}", if dev.cull { "on" } else { "off" }))
+        });
+        commands.register("pixel", "toggles pixel perfect rendering of the world", |dev: &mut DevSettings, _: &[&str]| {
+            if !dev.pixel_supported {
+                return Err("pixel perfect rendering needs MSAA to be off".to_string());
+            }
+            dev.pixel_perfect = !dev.pixel_perfect;
+            Ok(format!("pixel {}", if dev.pixel_perfect { "on" } else { "off" }))
+        });
+        commands.register("echo", "prints its arguments", |_: &mut DevSettings, args: &[&str]| Ok(args.join(" ")));
+        commands.set_var("stats_opacity", 1.0);
+        commands.set_var("stats_scale", 0.75);
         return Client {
             ortho: Mat4::identity(),
             rrs,
@@ -34,6 +135,27 @@ impl Client {
             square_render,
             textured_render,
             texture,
+            animations,
+            player,
+            graph,
+            minimap_texture,
+            post,
+            vignette,
+            pixel,
+            events,
+            text_render,
+            fonts,
+            sdf_font,
+            console,
+            commands,
+            dev: DevSettings {
+                show_stats: false,
+                vignette: true,
+                cull: true,
+                pixel_perfect: false,
+                pixel_supported,
+            },
+            stats: FrameStats::default(),
         };
     }
 }
@@ -48,74 +170,163 @@ fn regulate(f: f32, bound: f32) -> f32 {
 }
 
 impl win::Client for Client {
-    fn draw(&mut self, rc: &mut win::RenderContext) {
-        let time = rc.time();
-        let output = match rc.surface.get_current_texture() {
-            Ok(x) => x,
-            Err(_err) => return,
-        };
-        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let mut encoder = rc.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Render Encoder"),
-        });
+    fn update(&mut self, _rc: &mut win::RenderContext, dt: f32) {
+        self.player.update(&self.animations, dt);
+    }
 
+    fn draw(&mut self, rc: &mut win::RenderContext, view: &wgpu::TextureView, _alpha: f32) {
+        let time = rc.time();
         let mut rr = Record::new();
         let matrix = Mat4::box2d(vec2(100.0 + regulate(time, 2.0) * 500.0, 100.0), vec2(100.0, 100.0));
-        self.square_render.draw(rc, &mut rr, square::RenderParams { matrix, range: 0..3 });
+        self.square_render.draw(rc, &mut rr, square::RenderParams { matrix, range: 0..3, color: vec4(1.0, 1.0, 1.0, 1.0) });
         let matrix = Mat4::box2d(vec2(500.0 + regulate(time, 2.0) * 500.0, 300.0), vec2(100.0, 100.0));
-        self.square_render.draw(rc, &mut rr, square::RenderParams { matrix, range: 3..6 });
+        self.square_render.draw(rc, &mut rr, square::RenderParams { matrix, range: 3..6, color: vec4(1.0, 1.0, 1.0, 1.0) });
         let matrix = Mat4::box2d(vec2(300.0 + regulate(time, 2.0) * 500.0, 300.0), vec2(100.0, 100.0));
-        self.square_render.draw(rc, &mut rr, square::RenderParams { matrix, range: 3..6 });
+        self.square_render.draw(rc, &mut rr, square::RenderParams { matrix, range: 3..6, color: vec4(1.0, 1.0, 1.0, 1.0) });
         self.simple_render.draw(rc, &mut rr, ());
         let matrix = Mat4::box2d(vec2(100.0 + regulate(time, 2.0) * 500.0, 300.0), vec2(100.0, 100.0));
-        self.square_render.draw(rc, &mut rr, square::RenderParams { matrix, range: 3..6 });
+        self.square_render.draw(rc, &mut rr, square::RenderParams { matrix, range: 3..6, color: vec4(1.0, 1.0, 1.0, 1.0) });
         let matrix = Mat4::box2d(vec2(200.0 + regulate(time, 2.0) * 500.0, 300.0), vec2(100.0, 100.0));
-        self.square_render.draw(rc, &mut rr, square::RenderParams { matrix, range: 3..6 });
+        self.square_render.draw(rc, &mut rr, square::RenderParams { matrix, range: 3..6, color: vec4(1.0, 1.0, 1.0, 1.0) });
         let matrix = Mat4::box2d(vec2(400.0 + regulate(time, 2.0) * 500.0, 300.0), vec2(100.0, 100.0));
-        self.square_render.draw(rc, &mut rr, square::RenderParams { matrix, range: 3..6 });
+        self.square_render.draw(rc, &mut rr, square::RenderParams { matrix, range: 3..6, color: vec4(1.0, 1.0, 1.0, 1.0) });
         let matrix = Mat4::box2d(vec2(600.0 + regulate(time, 2.0) * 500.0, 300.0), vec2(100.0, 100.0));
-        self.square_render.draw(rc, &mut rr, square::RenderParams { matrix, range: 0..6 });
+        self.square_render.draw(rc, &mut rr, square::RenderParams { matrix, range: 0..6, color: vec4(1.0, 1.0, 1.0, 1.0) });
         let matrix = Mat4::box2d(vec2(400.0 + regulate(time, 2.0) * 500.0, 400.0), vec2(100.0, 100.0));
-        self.textured_render.draw(rc, &mut rr, textured::RenderParams { matrix, texture: self.texture });
+        self.textured_render.draw(rc, &mut rr, textured::RenderParams { matrix, texture: self.texture, uv: Rect::UNIT, tiles: vec2(1.0, 1.0) });
+        if let Some(uv) = self.player.uv(&self.animations) {
+            let matrix = Mat4::box2d(vec2(400.0 + regulate(time, 2.0) * 500.0, 520.0), vec2(100.0, 100.0));
+            self.textured_render.draw(rc, &mut rr, textured::RenderParams { matrix, texture: self.texture, uv, tiles: vec2(1.0, 1.0) });
+        }
 
         let settings = Settings {
             projection: self.ortho,
+            cull: self.dev.cull,
+            snap: None,
         };
-        {
-            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.2,
-                            b: 0.3,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
-
-            self.rrs.render(rc, &mut rpass, &rr, &settings);
+        // the world is laid out for 1280x720. The minimap shows all of it
+        // whatever its size, the pixel target has the same aspect ratio.
+        let design = vec2(1280.0, 720.0);
+        let minimap = Projection::new(Policy::Fit, design, self.graph.target("minimap").unwrap().size);
+        let minimap_settings = Settings { projection: minimap.matrix, ..settings };
+        let pixel = self.pixel.as_ref().filter(|_| self.dev.pixel_perfect).map(|pixel| (pixel, Settings {
+            cull: self.dev.cull,
+            ..pixel.settings(Projection::new(Policy::Stretch, design, pixel.resolution()).matrix)
+        }));
+        // the world goes through the pixel target, leaving the scene pass
+        // for the UI
+        let mut ui = Record::new();
+        let mut stats = FrameStats::default();
+        self.post.effects[self.vignette].enabled = self.dev.vignette;
+        self.console.update(rc.delta_time());
+        let minimap_texture = self.minimap_texture;
+        let result = self.graph.execute(rc, view, |ctx| match ctx.pass.name {
+            "minimap" => {
+                let (rc, mut rpass) = ctx.begin();
+                minimap.set_viewport(&mut rpass);
+                stats += self.rrs.render(rc, &mut rpass, &rr, &minimap_settings);
+            },
+            "pixel" => {
+                if let Some((pixel, pixel_settings)) = &pixel {
+                    let (rc, mut rpass) = ctx.begin_on(pixel.target());
+                    stats += self.rrs.render(rc, &mut rpass, &rr, pixel_settings);
+                }
+            },
+            "scene" => {
+                let record = if pixel.is_some() { &mut ui } else { &mut rr };
+                // framed by the sample image, its outer quarters as the border
+                let image = self.animations.sheet.size;
+                let size = vec2(image.width as f32, image.height as f32);
+                let frame = textured::NineSlice {
+                    uv: Rect::UNIT,
+                    size,
+                    insets: textured::Insets::from_center(size, Rect::from_center(size / 2.0, size / 2.0)),
+                    target: Rect::from_center(vec2(1000.0, 600.0), vec2(336.0, 196.0)),
+                    scale: 32.0 / size.x,
+                    mode: textured::SliceMode::Tile,
+                };
+                self.textured_render.draw_nine_slice(ctx.rc, record, self.texture, &frame);
+                let matrix = Mat4::box2d(vec2(1000.0, 600.0), vec2(320.0, 180.0));
+                self.textured_render.draw(ctx.rc, record, textured::RenderParams { matrix, texture: minimap_texture, uv: Rect::UNIT, tiles: vec2(1.0, 1.0) });
+                let (font_scale, font) = self.fonts.pick(ctx.rc.scale_factor()).unwrap();
+                self.console.font_scale = 1.0 / font_scale as f32;
+                self.console.draw(ctx.rc, record, &mut self.square_render, &mut self.text_render, *font);
+                if self.dev.show_stats {
+                    let color = vec4(1.0, 1.0, 1.0, self.commands.var("stats_opacity").unwrap_or(1.0));
+                    let text = format!("{:.1} ms\n{} culled", ctx.rc.delta_time() * 1000.0, self.stats.culled);
+                    let scale = self.commands.var("stats_scale").unwrap_or(0.75);
+                    let position = vec2(ctx.rc.logical_size().width - 200.0 * scale, 32.0 * scale);
+                    let effects = text::Effects {
+                        outline: Some(text::Outline { width: 1.0, color: vec4(0.0, 0.0, 0.0, color.w) }),
+                        shadow: Some(text::Shadow { offset: vec2(2.0, 2.0), softness: 2.0, color: vec4(0.0, 0.0, 0.0, 0.5 * color.w) }),
+                    };
+                    self.text_render.draw(ctx.rc, record, text::RenderParams {
+                        font: self.sdf_font, text, position, color, scale, effects,
+                    });
+                }
+                let scene = self.post.scene();
+                let (rc, mut rpass) = ctx.begin_on(scene);
+                if let Some((pixel, _)) = &pixel {
+                    pixel.draw(rc, &mut rpass, scene.size);
+                }
+                stats += self.rrs.render(rc, &mut rpass, record, &settings);
+            },
+            "post" => {
+                let view = ctx.view.unwrap();
+                self.post.apply(ctx.rc, ctx.encoder, view);
+            },
+            _ => (),
+        });
+        self.stats = stats;
+        // init validated the graph, so this only happens if it was changed
+        // since. execute checks before encoding, so the frame is skipped.
+        if let Err(err) = result {
+            eprintln!("Invalid frame graph, skipping the frame: {:?}", err);
         }
-
-        rc.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
     }
 
-    fn resize(&mut self, _rc: &mut win::RenderContext, size: winit::dpi::PhysicalSize<u32>) {
-        self.ortho = Mat4::ortho(size);
+    fn resize(&mut self, rc: &mut win::RenderContext, size: winit::dpi::PhysicalSize<u32>) {
+        // UI is laid out in logical pixels so it keeps its size on high DPI
+        // screens
+        self.ortho = Mat4::ortho_logical(size, rc.scale_factor());
+        self.post.resize(rc, size);
+        self.console.resize(rc.logical_size().width, self.console.visible_lines());
     }
 
     fn handle_event(&mut self, rc: &mut win::RenderContext, event: &winit::event::WindowEvent) -> win::EventState {
+        let console = &mut self.console;
+        let commands = &mut self.commands;
+        let dev = &mut self.dev;
+        self.events.dispatch(|layer| match layer {
+            Layer::Console if console.is_focused() => match console.receive_focused_event(event) {
+                ReceiveResult::Ignored => Response::Skipped,
+                ReceiveResult::Consumed => Response::Consumed,
+                ReceiveResult::Relinquish => Response::Relinquish,
+                ReceiveResult::Command(line) => {
+                    console.execute(commands, dev, &line);
+                    Response::Consumed
+                },
+            },
+            Layer::Console => Response::Skipped,
+            Layer::World => Self::handle_world_event(rc, console, event),
+        })
+    }
+}
+
+impl Client {
+    fn handle_world_event(rc: &mut win::RenderContext, console: &mut Console, event: &winit::event::WindowEvent) -> Response<Layer> {
         match event {
             winit::event::WindowEvent::KeyboardInput { device_id: _, event, is_synthetic: _, } => {
+                if event.state.is_pressed() && !event.repeat {
+                    match event.logical_key.as_ref() {
+                        winit::keyboard::Key::Named(winit::keyboard::NamedKey::Enter)
+                                | winit::keyboard::Key::Character("`") => {
+                            console.focus();
+                            return Response::Focus(Layer::Console);
+                        },
+                        _ => (),
+                    }
+                }
                 match event.logical_key {
                     winit::keyboard::Key::Named(named_key) => match named_key {
                         winit::keyboard::NamedKey::Escape => rc.exit(),
@@ -123,9 +334,9 @@ impl win::Client for Client {
                     },
                     _ => (),
                 };
-                return win::EventState::Consumed;
+                return Response::Consumed;
             },
-            _ => win::EventState::Skipped,
+            _ => Response::Skipped,
         }
     }
 }
@@ -0,0 +1,415 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use winit::{
+    event::{ElementState, KeyEvent, MouseScrollDelta, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::{
+    input::SCROLL_LINE_PIXELS,
+    mat::{vec2, vec4, Mat4, Vec2},
+    rrs::{Record, RenderConstruct},
+    square,
+    text::{self, BaseFontInfoContainer, FontInfoContainer, FontMetricsInfo},
+    util::{clampi, indirect_handles::WeakHandle},
+    win::RenderContext,
+};
+
+// An in-app developer console: scrollback history, a typing line and commands.
+// It only takes input while focused (see `receive_focused_event`) and fades
+// out a few seconds after the last message when unfocused.
+pub struct Console {
+    font_info: FontMetricsInfo,
+    visible_lines: i32,
+    line_height: f32,
+    history_length: i32,
+    typing: String,
+    history: Vec<String>,
+    history_split: Vec<String>,
+    width: f32,
+    height: f32,
+    flicker_timer: f32,
+    typing_flicker: bool,
+    fade_timer: f32,
+    scroll: i32, // a number from 0 to max_scroll for the scroll value
+    max_scroll: i32, // cache the maximum we calculated you could scroll
+    scroll_float: f32, // the extra partial scroll value from scrolling that is not aligned
+    pub scroll_speed: f32,
+    // top left corner
+    pub position: Vec2,
+    // what text is drawn at, e.g. 0.5 for a font rasterized at twice the
+    // size of the metrics for high DPI screens
+    pub font_scale: f32,
+}
+
+pub const BAR_FLICKER_TIME: f32 = 0.6;
+pub const FADE_START_TIME: f32 = 3.0;
+pub const FADE_TIME: f32 = 1.0;
+
+pub enum ReceiveResult {
+    Ignored,
+    Consumed,
+    Relinquish,
+    Command(String),
+}
+
+impl ReceiveResult {
+    pub fn consumed(&self) -> bool {
+        !matches!(self, Self::Ignored)
+    }
+    pub fn relinquished(&self) -> bool {
+        matches!(self, Self::Relinquish)
+    }
+    pub fn get_command(&self) -> Option<String> {
+        match self {
+            Self::Command(cmd) => Some(cmd.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl Console {
+    pub fn new(font_info: FontMetricsInfo, line_height: f32, history_length: i32) -> Self {
+        assert!(history_length >= 0 && line_height >= 0.0);
+        Console {
+            font_info,
+            visible_lines: 0,
+            line_height,
+            history_length,
+            typing: String::new(),
+            history: Vec::new(),
+            history_split: Vec::new(),
+            width: 800.0,
+            height: 0.0,
+            flicker_timer: 0.0,
+            typing_flicker: false,
+            fade_timer: f32::MAX,
+            scroll: 0,
+            max_scroll: 0,
+            scroll_float: 0.0,
+            scroll_speed: 0.05,
+            position: vec2(0.0, 0.0),
+            font_scale: 1.0,
+        }
+    }
+
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+    pub fn visible_lines(&self) -> i32 {
+        self.visible_lines
+    }
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn set_scroll(&mut self, scroll: i32) {
+        self.scroll = scroll.clamp(0, self.max_scroll.max(0));
+        self.scroll_float = 0.0;
+        self.regen_split();
+    }
+    pub fn max_scroll(&self) -> i32 {
+        self.max_scroll
+    }
+
+    pub fn resize(&mut self, width: f32, visible_lines: i32) {
+        assert!(visible_lines >= 0 && width >= 0.0);
+        self.width = width;
+        self.visible_lines = visible_lines;
+        self.height = visible_lines as f32 * self.line_height + self.line_height / 4.0;
+        self.regen_split()
+    }
+
+    fn regen_split(&mut self) {
+        // resolve scrolling
+        let line_change = f32::round(self.scroll_float * self.scroll_speed) as i32;
+        self.scroll_float -= line_change as f32 / self.scroll_speed;
+        self.scroll = i32::min(self.max_scroll, i32::max(0, self.scroll + line_change));
+
+        let wrap: Vec<String> = self.font_info.split_lines(&self.history.join("\n"), Some(self.width)).collect();
+        let pos = wrap.len() as i32 - self.visible_lines - self.scroll;
+        let pos = clampi(pos, 0, clampi(wrap.len() as i32 - self.visible_lines, 0, wrap.len() as i32));
+        let wrap_range = &wrap[
+            pos as usize..
+            clampi(pos + self.visible_lines, 0, wrap.len() as i32) as usize];
+        self.max_scroll = std::cmp::max(0, wrap.len() as i32 - self.visible_lines);
+        self.history_split = wrap_range.to_vec();
+    }
+
+    pub fn println(&mut self, line: &str) {
+        let split = self.font_info.split_lines(line, None);
+        // take the last x lines
+        let add: Vec<String> = split.collect();
+        let add = &add[std::cmp::max(0, add.len() as i32 - self.history_length) as usize..add.len()];
+        let history_remove =
+            std::cmp::max(0, self.history.len() as i32 - (self.history_length - add.len() as i32)) as usize;
+        self.history.drain(0..history_remove);
+        self.history.extend(add.iter().cloned());
+
+        self.regen_split();
+        self.fade_timer = 0.0;
+    }
+
+    fn get_visible_history_empty_lines(&self) -> i32 {
+        std::cmp::max(0, self.visible_lines - self.history_split.len() as i32)
+    }
+
+    pub fn get_visible_history(&self) -> &Vec<String> {
+        &self.history_split
+    }
+
+    pub fn get_typing(&self) -> &String {
+        &self.typing
+    }
+
+    pub fn add_typing(&mut self, c: char) {
+        self.typing.push(c);
+    }
+
+    pub fn add_typing_lines(&mut self, s: &str) {
+        self.typing += s;
+    }
+
+    pub fn remove_typing(&mut self, count: i32) {
+        assert!(count >= 0);
+        for _ in 0..count {
+            if self.typing.pop().is_none() {
+                break
+            }
+        }
+    }
+
+    pub fn erase_typing(&mut self) {
+        self.typing.clear();
+    }
+
+    pub fn set_typing_flicker(&mut self, typing_flicker: bool) {
+        self.typing_flicker = typing_flicker;
+        self.flicker_timer = 0.0;
+        self.fade_timer = 0.0;
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.fade_timer += delta_time;
+        if self.typing_flicker {
+            self.flicker_timer += delta_time;
+            while self.flicker_timer > BAR_FLICKER_TIME {
+                self.flicker_timer -= BAR_FLICKER_TIME;
+            }
+        }
+    }
+
+    // Draws the background with the square renderer and each line with the
+    // text renderer. Nothing is drawn once the console has faded out.
+    pub fn draw(
+        &self,
+        rc: &mut RenderContext,
+        record: &mut Record,
+        square_render: &mut square::Construct,
+        text_render: &mut text::Construct,
+        font: WeakHandle<text::Font>,
+    ) {
+        let is_fade = self.fade_timer > FADE_START_TIME && !self.typing_flicker;
+        let mut fade = 1.0;
+        if is_fade {
+            fade = 1.0 - f32::min(1.0, (self.fade_timer - FADE_START_TIME) / FADE_TIME);
+        }
+        if fade <= 0.0 {
+            return;
+        }
+
+        let color = vec4(1.0, 1.0, 1.0, fade);
+        let background_color = vec4(0.0, 0.0, 0.0, 0.6 * fade);
+        let position = self.position;
+
+        let effective_height = self.height + if self.typing_flicker { self.line_height } else { 0.0 };
+        let matrix = Mat4::box2d(
+            vec2(position.x + self.width / 2.0, position.y + effective_height / 2.0),
+            vec2(self.width, effective_height));
+        square_render.draw(rc, record, square::RenderParams { matrix, range: 0..6, color: background_color });
+
+        let mut pos = vec2(
+            position.x,
+            position.y + self.line_height * (self.get_visible_history_empty_lines() + 1) as f32
+        );
+        for line in self.get_visible_history() {
+            text_render.draw(rc, record, text::RenderParams {
+                font, text: line.clone(), position: pos, color, scale: self.font_scale, effects: Default::default(),
+            });
+            pos.y += self.line_height;
+        }
+
+        if self.typing_flicker {
+            let mut typing_line = "> ".to_string() + &self.typing;
+            if self.flicker_timer > BAR_FLICKER_TIME / 2.0 {
+                typing_line.push('|');
+            }
+            text_render.draw(rc, record, text::RenderParams {
+                font, text: typing_line, position: pos, color, scale: self.font_scale, effects: Default::default(),
+            });
+        }
+    }
+
+    pub fn focus(&mut self) {
+        self.set_typing_flicker(true);
+        self.set_scroll(0);
+    }
+
+    pub fn unfocus(&mut self) {
+        self.set_typing_flicker(false);
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.typing_flicker
+    }
+
+    pub fn process_scroll(&mut self, scroll_y: f32) {
+        self.scroll_float += scroll_y;
+        self.regen_split();
+    }
+
+    pub fn receive_focused_event(&mut self, event: &WindowEvent) -> ReceiveResult {
+        match event {
+            WindowEvent::KeyboardInput {
+                event: KeyEvent {
+                    physical_key: PhysicalKey::Code(key),
+                    state: ElementState::Pressed,
+                    text,
+                    ..
+                }, ..
+            } => {
+                match key {
+                    KeyCode::Escape => {
+                        self.set_typing_flicker(false);
+                        return ReceiveResult::Relinquish;
+                    },
+                    KeyCode::Enter | KeyCode::NumpadEnter => {
+                        if self.get_typing().is_empty() {
+                            self.set_typing_flicker(false);
+                            return ReceiveResult::Relinquish;
+                        } else {
+                            let typing = self.get_typing().clone();
+                            self.erase_typing();
+                            return ReceiveResult::Command(typing);
+                        }
+                    },
+                    KeyCode::Backspace => {
+                        self.remove_typing(1);
+                        return ReceiveResult::Consumed;
+                    },
+                    _ => (),
+                };
+                // add plaintext (only valid characters)
+                if let Some(text) = text {
+                    for c in text.chars().filter(|c| !c.is_control()) {
+                        if self.font_info.is_char_valid(&c) {
+                            self.add_typing(c);
+                        }
+                    }
+                }
+
+                // for now we just consume all keyboard inputs anyways
+                ReceiveResult::Consumed
+            },
+            // grab mouse wheel events, this just scrolls the history
+            WindowEvent::MouseWheel { delta, .. } => {
+                let dy = match delta {
+                    MouseScrollDelta::LineDelta(_, dy) => dy * SCROLL_LINE_PIXELS,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32,
+                };
+                self.process_scroll(dy);
+                ReceiveResult::Consumed
+            },
+            _ => ReceiveResult::Ignored,
+        }
+    }
+
+    // Echoes the command, runs it and prints its output or error
+    pub fn execute<T>(&mut self, commands: &mut CommandRegistry<T>, state: &mut T, line: &str) {
+        self.println(&format!("> {}", line));
+        match commands.run(state, line) {
+            Ok(output) if output.is_empty() => (),
+            Ok(output) => self.println(&output),
+            Err(err) => self.println(&format!("error: {}", err)),
+        }
+    }
+}
+
+pub type CommandFn<T> = Box<dyn FnMut(&mut T, &[&str]) -> Result<String, String>>;
+
+struct Command<T> {
+    help: String,
+    run: CommandFn<T>,
+}
+
+// Named commands run against some app state `T`, plus string variables that
+// the app reads back with `var`. `help`, `set`, `get` and `vars` are built in
+// unless a registered command has the same name.
+pub struct CommandRegistry<T> {
+    commands: BTreeMap<String, Command<T>>,
+    vars: BTreeMap<String, String>,
+}
+
+impl<T> Default for CommandRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> CommandRegistry<T> {
+    pub fn new() -> CommandRegistry<T> {
+        CommandRegistry {
+            commands: BTreeMap::new(),
+            vars: BTreeMap::new(),
+        }
+    }
+
+    pub fn register<F>(&mut self, name: &str, help: &str, run: F)
+            where F: FnMut(&mut T, &[&str]) -> Result<String, String> + 'static {
+        self.commands.insert(name.to_string(), Command {
+            help: help.to_string(),
+            run: Box::new(run),
+        });
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.commands.keys().map(String::as_str)
+    }
+
+    pub fn set_var(&mut self, name: &str, value: impl ToString) {
+        self.vars.insert(name.to_string(), value.to_string());
+    }
+
+    pub fn var<V: FromStr>(&self, name: &str) -> Option<V> {
+        self.vars.get(name)?.parse().ok()
+    }
+
+    // Splits the line on whitespace and runs the named command with the rest
+    // as arguments
+    pub fn run(&mut self, state: &mut T, line: &str) -> Result<String, String> {
+        let mut words = line.split_whitespace();
+        let Some(name) = words.next() else { return Ok(String::new()) };
+        let args: Vec<&str> = words.collect();
+        if let Some(command) = self.commands.get_mut(name) {
+            return (command.run)(state, &args);
+        }
+        match (name, args.as_slice()) {
+            ("help", []) => Ok(self.commands.iter()
+                .map(|(name, command)| format!("{} - {}", name, command.help))
+                .chain(["help, set <var> <value>, get <var>, vars".to_string()])
+                .collect::<Vec<_>>()
+                .join("\n")),
+            ("set", [var, value @ ..]) if !value.is_empty() => {
+                self.set_var(var, value.join(" "));
+                Ok(String::new())
+            },
+            ("get", [var]) => self.vars.get(*var).cloned().ok_or_else(|| format!("{} is not set", var)),
+            ("vars", []) => Ok(self.vars.iter()
+                .map(|(name, value)| format!("{} = {}", name, value))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            ("help" | "set" | "get" | "vars", _) => Err(format!("Wrong arguments for {}, see help", name)),
+            _ => Err(format!("Unknown command: {}", name)),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use crate::win::EventState;
+
+// What a handler did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Response<K> {
+    // pass the event on to the next handler
+    Skipped,
+    // stop propagation
+    Consumed,
+    // stop propagation and give up focus, like closing a text box with Escape
+    Relinquish,
+    // stop propagation and move focus to another handler
+    Focus(K),
+}
+
+impl<K> From<EventState> for Response<K> {
+    fn from(state: EventState) -> Self {
+        match state {
+            EventState::Consumed => Response::Consumed,
+            EventState::Skipped => Response::Skipped,
+        }
+    }
+}
+
+// Decides which handlers see an event and in what order. Handlers are
+// identified by key and stay owned by the client, which does the actual
+// handling in the closure passed to `dispatch`:
+//
+//     self.events.dispatch(|layer| match layer {
+//         Layer::Console => self.console.handle_event(event),
+//         Layer::World => self.world.handle_event(event).into(),
+//     })
+//
+// The focused handler goes first, then the rest by descending priority.
+// Handlers with equal priority keep their registration order.
+pub struct EventDispatcher<K: Copy + Eq> {
+    handlers: Vec<(K, i32)>,
+    focused: Option<K>,
+}
+
+impl<K: Copy + Eq> Default for EventDispatcher<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Copy + Eq> EventDispatcher<K> {
+    pub fn new() -> EventDispatcher<K> {
+        EventDispatcher {
+            handlers: vec![],
+            focused: None,
+        }
+    }
+
+    // Registering a key again only changes its priority
+    pub fn register(&mut self, key: K, priority: i32) {
+        self.handlers.retain(|(other, _)| *other != key);
+        let index = self.handlers.iter()
+            .position(|(_, other)| *other < priority)
+            .unwrap_or(self.handlers.len());
+        self.handlers.insert(index, (key, priority));
+    }
+
+    pub fn unregister(&mut self, key: K) {
+        self.handlers.retain(|(other, _)| *other != key);
+        if self.focused == Some(key) {
+            self.focused = None;
+        }
+    }
+
+    pub fn is_registered(&self, key: K) -> bool {
+        self.handlers.iter().any(|(other, _)| *other == key)
+    }
+
+    // Focusing an unregistered key does nothing
+    pub fn focus(&mut self, key: K) {
+        if self.is_registered(key) {
+            self.focused = Some(key);
+        }
+    }
+
+    pub fn blur(&mut self) {
+        self.focused = None;
+    }
+
+    pub fn focused(&self) -> Option<K> {
+        self.focused
+    }
+
+    pub fn order(&self) -> impl Iterator<Item = K> + '_ {
+        let focused = self.focused;
+        focused.into_iter()
+            .chain(self.handlers.iter().map(|(key, _)| *key).filter(move |key| Some(*key) != focused))
+    }
+
+    pub fn dispatch<F>(&mut self, mut handle: F) -> EventState
+            where F: FnMut(K) -> Response<K> {
+        let order: Vec<K> = self.order().collect();
+        for key in order {
+            match handle(key) {
+                Response::Skipped => continue,
+                Response::Consumed => (),
+                Response::Relinquish => {
+                    if self.focused == Some(key) {
+                        self.focused = None;
+                    }
+                },
+                Response::Focus(other) => self.focus(other),
+            }
+            return EventState::Consumed;
+        }
+        EventState::Skipped
+    }
+}
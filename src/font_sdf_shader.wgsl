@@ -0,0 +1,67 @@
+const vertices = array<u32, 6 * 2>(
+    0, 0,
+    1, 0,
+    1, 1,
+    1, 1,
+    0, 1,
+    0, 0,
+);
+
+@group(0)
+@binding(0)
+var<uniform> projection: mat4x4<f32>;
+
+struct InstanceBuffer {
+    @location(0) model_matrix_0: vec4<f32>,
+    @location(1) model_matrix_1: vec4<f32>,
+    @location(2) model_matrix_2: vec4<f32>,
+    @location(3) model_matrix_3: vec4<f32>,
+    @location(4) texture_pos: vec2<f32>,
+    @location(5) texture_scale: vec2<f32>,
+    @location(6) color: vec4<f32>,
+    @location(7) outline_color: vec4<f32>,
+    @location(8) effect: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) outline_color: vec4<f32>,
+    @location(3) effect: vec4<f32>,
+};
+
+// glyph quads span 0..1 from their top left corner
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32,
+            buffer: InstanceBuffer) -> VertexOutput {
+    let mat = mat4x4<f32>(buffer.model_matrix_0, buffer.model_matrix_1, buffer.model_matrix_2, buffer.model_matrix_3);
+    let x = f32(vertices[in_vertex_index * 2]);
+    let y = f32(vertices[in_vertex_index * 2 + 1]);
+    var out: VertexOutput;
+    out.clip_position = projection * mat * vec4<f32>(x, y, 0.0, 1.0);
+    out.tex_coords = buffer.texture_pos + vec2(x, y) * buffer.texture_scale;
+    out.color = buffer.color;
+    out.outline_color = buffer.outline_color;
+    out.effect = buffer.effect;
+    return out;
+}
+
+@group(0) @binding(1) var glyph_texture: texture_2d<f32>;
+@group(0) @binding(2) var glyph_sampler: sampler;
+
+// effect is (outline width, softness, spread, unused), all in atlas pixels
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let outline = in.effect.x;
+    let softness = in.effect.y;
+    let spread = in.effect.z;
+    // signed distance to the glyph's edge, positive inside
+    let distance = (textureSample(glyph_texture, glyph_sampler, in.tex_coords).r - 0.5) * 2.0 * spread;
+    // half a screen pixel, keeps the edge one pixel wide at any scale
+    let aa = max(fwidth(distance) * 0.5, 0.0001);
+    let fill = smoothstep(-aa, aa, distance);
+    let outer = smoothstep(-outline - softness - aa, -outline + aa, distance);
+    let color = mix(in.outline_color, in.color, fill);
+    return vec4<f32>(color.rgb, color.a * outer);
+}
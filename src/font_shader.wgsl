@@ -1,21 +1,25 @@
-// Vertex shader
-struct CameraUniform {
-    view_proj: mat4x4<f32>,
-};
-
-struct InstanceInput {
-    @location(5) model_matrix_0: vec4<f32>,
-    @location(6) model_matrix_1: vec4<f32>,
-    @location(7) model_matrix_2: vec4<f32>,
-    @location(8) model_matrix_3: vec4<f32>, 
-    @location(9) texture_pos: vec2<f32>,
-    @location(10) texture_scale: vec2<f32>,
-    @location(11) color: vec4<f32>,
-};
-
-struct VertexInput {
-    @location(0) position: vec2<f32>,
-};
+const vertices = array<u32, 6 * 2>(
+    0, 0,
+    1, 0,
+    1, 1,
+    1, 1,
+    0, 1,
+    0, 0,
+);
+
+@group(0)
+@binding(0)
+var<uniform> projection: mat4x4<f32>;
+
+struct InstanceBuffer {
+    @location(0) model_matrix_0: vec4<f32>,
+    @location(1) model_matrix_1: vec4<f32>,
+    @location(2) model_matrix_2: vec4<f32>,
+    @location(3) model_matrix_3: vec4<f32>,
+    @location(4) texture_pos: vec2<f32>,
+    @location(5) texture_scale: vec2<f32>,
+    @location(6) color: vec4<f32>,
+}
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
@@ -23,39 +27,25 @@ struct VertexOutput {
     @location(1) color: vec4<f32>,
 };
 
+// glyph quads span 0..1 from their top left corner
 @vertex
-fn vs_main(
-    model: VertexInput,
-    instance: InstanceInput,
-) -> VertexOutput {
-    let model_matrix = mat4x4<f32>(
-        instance.model_matrix_0,
-        instance.model_matrix_1,
-        instance.model_matrix_2,
-        instance.model_matrix_3,
-    );
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32,
+            buffer: InstanceBuffer) -> VertexOutput {
+    let mat = mat4x4<f32>(buffer.model_matrix_0, buffer.model_matrix_1, buffer.model_matrix_2, buffer.model_matrix_3);
+    let x = f32(vertices[in_vertex_index * 2]);
+    let y = f32(vertices[in_vertex_index * 2 + 1]);
     var out: VertexOutput;
-    out.tex_coords = instance.texture_pos +
-        vec2(
-            model.position.x * instance.texture_scale.x,
-            model.position.y * instance.texture_scale.y
-        );
-    out.clip_position = model_matrix * vec4<f32>(model.position, 0.0, 1.0);
-    out.color = instance.color;
+    out.clip_position = projection * mat * vec4<f32>(x, y, 0.0, 1.0);
+    out.tex_coords = buffer.texture_pos + vec2(x, y) * buffer.texture_scale;
+    out.color = buffer.color;
     return out;
 }
 
-// Fragment shader
-
-@group(0) @binding(0)
-var t_diffuse: texture_2d<f32>;
-@group(0) @binding(1)
-var s_diffuse: sampler;
+@group(0) @binding(1) var glyph_texture: texture_2d<f32>;
+@group(0) @binding(2) var glyph_sampler: sampler;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let r = textureSample(t_diffuse, s_diffuse, in.tex_coords).r;
-    return in.color * vec4(1.0, 1.0, 1.0, r);
+    let coverage = textureSample(glyph_texture, glyph_sampler, in.tex_coords).r;
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
 }
-
-
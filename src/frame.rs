@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use crate::{target::{color_attachment, RenderTarget}, win::RenderContext};
+
+pub type PassId = &'static str;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attachment {
+    // the swapchain texture acquired for this frame
+    Surface,
+    // a render target owned by the graph
+    Target(&'static str),
+    // the pass renders into a target the graph doesn't own (see PassContext::begin_on)
+    External,
+}
+
+#[derive(Debug, Clone)]
+pub struct Pass {
+    pub name: PassId,
+    pub attachment: Attachment,
+    // None clears to the graph's clear color
+    pub load: Option<wgpu::LoadOp<wgpu::Color>>,
+    pub dependencies: Vec<PassId>,
+}
+
+#[derive(Debug)]
+pub enum FrameError {
+    MissingDependency(PassId, PassId),
+    MissingTarget(PassId, &'static str),
+    Cycle(PassId),
+}
+
+pub struct FrameGraph {
+    pub clear_color: wgpu::Color,
+    passes: Vec<Pass>,
+    targets: HashMap<&'static str, RenderTarget>,
+}
+
+pub struct PassContext<'a> {
+    pub rc: &'a mut RenderContext,
+    pub encoder: &'a mut wgpu::CommandEncoder,
+    pub pass: &'a Pass,
+    pub view: Option<&'a wgpu::TextureView>,
+    msaa: Option<&'a wgpu::TextureView>,
+    load: wgpu::LoadOp<wgpu::Color>,
+}
+
+impl Pass {
+    pub fn new(name: PassId, attachment: Attachment) -> Pass {
+        Pass {
+            name,
+            attachment,
+            load: None,
+            dependencies: vec![],
+        }
+    }
+
+    pub fn clear(mut self, color: wgpu::Color) -> Pass {
+        self.load = Some(wgpu::LoadOp::Clear(color));
+        self
+    }
+
+    pub fn keep(mut self) -> Pass {
+        self.load = Some(wgpu::LoadOp::Load);
+        self
+    }
+
+    pub fn after(mut self, dependency: PassId) -> Pass {
+        self.dependencies.push(dependency);
+        self
+    }
+}
+
+impl<'a> PassContext<'a> {
+    // Begins a render pass on the pass's attachment with its load op. The
+    // render context is handed back alongside since the pass borrows the encoder.
+    pub fn begin(&mut self) -> (&mut RenderContext, wgpu::RenderPass<'_>) {
+        let view = self.view.expect("Pass has no attachment, use begin_on");
+        let attachment = color_attachment(view, self.msaa, self.load);
+        Self::begin_pass(self.rc, self.encoder, self.pass, attachment)
+    }
+
+    pub fn begin_on<'b>(&'b mut self, target: &'b RenderTarget) -> (&'b mut RenderContext, wgpu::RenderPass<'b>) {
+        let attachment = target.attachment(self.load);
+        Self::begin_pass(self.rc, self.encoder, self.pass, attachment)
+    }
+
+    fn begin_pass<'b>(
+        rc: &'b mut RenderContext,
+        encoder: &'b mut wgpu::CommandEncoder,
+        pass: &Pass,
+        attachment: wgpu::RenderPassColorAttachment<'b>,
+    ) -> (&'b mut RenderContext, wgpu::RenderPass<'b>) {
+        let rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(pass.name),
+            color_attachments: &[Some(attachment)],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        (rc, rpass)
+    }
+}
+
+impl Default for FrameGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameGraph {
+    pub fn new() -> FrameGraph {
+        FrameGraph {
+            clear_color: wgpu::Color::BLACK,
+            passes: vec![],
+            targets: HashMap::new(),
+        }
+    }
+
+    pub fn add_pass(&mut self, pass: Pass) {
+        self.passes.push(pass);
+    }
+
+    pub fn remove_pass(&mut self, name: PassId) -> Option<Pass> {
+        let index = self.passes.iter().position(|pass| pass.name == name)?;
+        Some(self.passes.remove(index))
+    }
+
+    pub fn passes(&self) -> &[Pass] {
+        &self.passes
+    }
+
+    pub fn add_target(&mut self, name: &'static str, target: RenderTarget) {
+        self.targets.insert(name, target);
+    }
+
+    pub fn target(&self, name: &'static str) -> Option<&RenderTarget> {
+        self.targets.get(name)
+    }
+
+    // Pass indices in an order where every pass runs after its dependencies.
+    // Passes without dependencies between them keep their insertion order.
+    pub fn order(&self) -> Result<Vec<usize>, FrameError> {
+        let index_of: HashMap<PassId, usize> = self.passes.iter()
+            .enumerate()
+            .map(|(index, pass)| (pass.name, index))
+            .collect();
+        for pass in &self.passes {
+            for dependency in &pass.dependencies {
+                if !index_of.contains_key(dependency) {
+                    return Err(FrameError::MissingDependency(pass.name, dependency));
+                }
+            }
+        }
+        let mut order = Vec::with_capacity(self.passes.len());
+        let mut done = vec![false; self.passes.len()];
+        while order.len() < self.passes.len() {
+            let next = self.passes.iter().enumerate().position(|(index, pass)| {
+                !done[index] && pass.dependencies.iter().all(|dependency| done[index_of[dependency]])
+            });
+            match next {
+                Some(index) => {
+                    done[index] = true;
+                    order.push(index);
+                },
+                None => {
+                    let stuck = self.passes.iter().enumerate().find(|(index, _)| !done[*index]).unwrap().1;
+                    return Err(FrameError::Cycle(stuck.name));
+                },
+            }
+        }
+        Ok(order)
+    }
+
+    // Checks for everything execute would fail on, missing dependencies or
+    // targets and cycles, so graphs that don't change can be checked once
+    pub fn validate(&self) -> Result<(), FrameError> {
+        self.order()?;
+        for pass in &self.passes {
+            if let Attachment::Target(name) = pass.attachment {
+                if !self.targets.contains_key(name) {
+                    return Err(FrameError::MissingTarget(pass.name, name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Runs every pass in dependency order, with Attachment::Surface passes
+    // drawing to `surface_view`. Each pass is recorded into its own encoder and
+    // submitted before the next one, since renderers stage their instance data
+    // with queue.write_buffer.
+    pub fn execute<F>(&self, rc: &mut RenderContext, surface_view: &wgpu::TextureView, mut draw: F) -> Result<(), FrameError>
+            where F: FnMut(&mut PassContext) {
+        // nothing is encoded for graphs that would fail part way
+        self.validate()?;
+        let order = self.order()?;
+        let surface_msaa = rc.msaa_view.clone();
+        for index in order {
+            let pass = &self.passes[index];
+            let (view, msaa) = match pass.attachment {
+                Attachment::Surface => (Some(surface_view), surface_msaa.as_ref()),
+                Attachment::Target(name) => {
+                    let target = &self.targets[name];
+                    (Some(target.view()), target.msaa_view.as_ref())
+                },
+                Attachment::External => (None, None),
+            };
+            let mut encoder = rc.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some(pass.name),
+            });
+            draw(&mut PassContext {
+                rc,
+                encoder: &mut encoder,
+                pass,
+                view,
+                msaa,
+                load: pass.load.unwrap_or(wgpu::LoadOp::Clear(self.clear_color)),
+            });
+            rc.queue.submit(std::iter::once(encoder.finish()));
+        }
+        Ok(())
+    }
+}
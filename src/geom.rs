@@ -0,0 +1,404 @@
+use crate::mat::{vec2, vec4, Mat4, Vec2};
+
+// 2D shapes for culling, picking and layout, in the same units as whatever
+// they're compared against. Edges count as inside, so shapes that only touch
+// still overlap.
+
+// Axis aligned rectangle
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Circle {
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
+// Casts return distances along the ray in multiples of `direction`, so a
+// normalized direction gives distances in world units
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vec2,
+    pub direction: Vec2,
+}
+
+// Oriented box. `axes` go from the center to the middle of two neighbouring
+// sides, so the box is center + u * axes[0] + v * axes[1] for u, v in -1..1.
+// Axes don't have to be perpendicular, which keeps transform exact under
+// shear.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Obb {
+    pub center: Vec2,
+    pub axes: [Vec2; 2],
+}
+
+fn transform_point(matrix: &Mat4, p: Vec2) -> Vec2 {
+    let p = matrix % &vec4(p.x, p.y, 0.0, 1.0);
+    vec2(p.x, p.y)
+}
+
+fn transform_vector(matrix: &Mat4, v: Vec2) -> Vec2 {
+    let v = matrix % &vec4(v.x, v.y, 0.0, 0.0);
+    vec2(v.x, v.y)
+}
+
+// Range of t where origin + t * direction is within min..max on both axes
+fn slabs(origin: Vec2, direction: Vec2, min: Vec2, max: Vec2) -> Option<(f32, f32)> {
+    let mut near = f32::NEG_INFINITY;
+    let mut far = f32::INFINITY;
+    for axis in 0..2 {
+        let (o, d) = (origin.data[axis], direction.data[axis]);
+        let (lo, hi) = (min.data[axis], max.data[axis]);
+        if d == 0.0 {
+            if o < lo || o > hi {
+                return None;
+            }
+            continue;
+        }
+        let (a, b) = ((lo - o) / d, (hi - o) / d);
+        near = near.max(a.min(b));
+        far = far.min(a.max(b));
+    }
+    (near <= far).then_some((near, far))
+}
+
+impl Rect {
+    // 0..1 on both axes, like the uvs of a whole texture
+    pub const UNIT: Rect = Rect { min: Vec2 { data: [0.0, 0.0] }, max: Vec2 { data: [1.0, 1.0] } };
+
+    pub fn new(min: Vec2, max: Vec2) -> Rect {
+        Rect { min, max }
+    }
+
+    // Any two opposite corners
+    pub fn from_corners(a: Vec2, b: Vec2) -> Rect {
+        Rect { min: a.min(b), max: a.max(b) }
+    }
+
+    // Same placement as Mat4::box2d
+    pub fn from_center(center: Vec2, size: Vec2) -> Rect {
+        let half = size.abs() / 2.0;
+        Rect { min: center - half, max: center + half }
+    }
+
+    // `position` is the top left corner in y down coordinates
+    pub fn from_pos_size(position: Vec2, size: Vec2) -> Rect {
+        Rect::from_corners(position, position + size)
+    }
+
+    // The smallest rect containing every point, None for no points
+    pub fn from_points(points: impl IntoIterator<Item = Vec2>) -> Option<Rect> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Rect { min: first, max: first }, |rect, point| rect.expand_to(point)))
+    }
+
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    pub fn width(&self) -> f32 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max.y - self.min.y
+    }
+
+    pub fn area(&self) -> f32 {
+        self.width() * self.height()
+    }
+
+    // Inverted on some axis. A rect with zero width or height isn't empty,
+    // it still contains the points on it.
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y
+    }
+
+    // In y up order: bottom left, bottom right, top right, top left
+    pub fn corners(&self) -> [Vec2; 4] {
+        [self.min, vec2(self.max.x, self.min.y), self.max, vec2(self.min.x, self.max.y)]
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.min.x <= point.x && point.x <= self.max.x &&
+        self.min.y <= point.y && point.y <= self.max.y
+    }
+
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        self.contains(other.min) && self.contains(other.max)
+    }
+
+    pub fn overlaps(&self, other: &Rect) -> bool {
+        self.min.x <= other.max.x && other.min.x <= self.max.x &&
+        self.min.y <= other.max.y && other.min.y <= self.max.y
+    }
+
+    pub fn overlaps_circle(&self, circle: &Circle) -> bool {
+        circle.overlaps_rect(self)
+    }
+
+    // Some exactly when the rects overlap
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let rect = Rect { min: self.min.max(other.min), max: self.max.min(other.max) };
+        (!rect.is_empty()).then_some(rect)
+    }
+
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect { min: self.min.min(other.min), max: self.max.max(other.max) }
+    }
+
+    pub fn expand_to(&self, point: Vec2) -> Rect {
+        Rect { min: self.min.min(point), max: self.max.max(point) }
+    }
+
+    // Moves every side out by `amount`, or in when it's negative
+    pub fn grow(&self, amount: f32) -> Rect {
+        Rect { min: self.min - Vec2::splat(amount), max: self.max + Vec2::splat(amount) }
+    }
+
+    pub fn translate(&self, offset: Vec2) -> Rect {
+        Rect { min: self.min + offset, max: self.max + offset }
+    }
+
+    // The point in the rect closest to `point`, which is `point` when it's
+    // inside
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        point.max(self.min).min(self.max)
+    }
+
+    pub fn distance(&self, point: Vec2) -> f32 {
+        self.closest_point(point).distance(point)
+    }
+
+    // The axis aligned bounds of the transformed rect. Use Obb::from_rect
+    // to keep the rotation.
+    pub fn transform(&self, matrix: &Mat4) -> Rect {
+        Rect::from_points(self.corners().map(|corner| transform_point(matrix, corner))).unwrap()
+    }
+}
+
+impl Circle {
+    pub fn new(center: Vec2, radius: f32) -> Circle {
+        Circle { center, radius }
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::from_center(self.center, Vec2::splat(self.radius * 2.0))
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.center.distance(point) <= self.radius
+    }
+
+    pub fn overlaps(&self, other: &Circle) -> bool {
+        self.center.distance(other.center) <= self.radius + other.radius
+    }
+
+    pub fn overlaps_rect(&self, rect: &Rect) -> bool {
+        rect.distance(self.center) <= self.radius
+    }
+
+    // Scales the radius by the transform's largest axis, so non-uniform
+    // scaling gives a circle around the resulting ellipse
+    pub fn transform(&self, matrix: &Mat4) -> Circle {
+        let scale = transform_vector(matrix, vec2(1.0, 0.0)).length()
+            .max(transform_vector(matrix, vec2(0.0, 1.0)).length());
+        Circle { center: transform_point(matrix, self.center), radius: self.radius * scale }
+    }
+}
+
+impl Segment {
+    pub fn new(start: Vec2, end: Vec2) -> Segment {
+        Segment { start, end }
+    }
+
+    pub fn direction(&self) -> Vec2 {
+        self.end - self.start
+    }
+
+    pub fn length(&self) -> f32 {
+        self.direction().length()
+    }
+
+    // start at 0, end at 1
+    pub fn at(&self, t: f32) -> Vec2 {
+        self.start.lerp(self.end, t)
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::from_corners(self.start, self.end)
+    }
+
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        let direction = self.direction();
+        let length_squared = direction.length_squared();
+        if length_squared == 0.0 {
+            return self.start;
+        }
+        self.at(((point - self.start).dot(direction) / length_squared).clamp(0.0, 1.0))
+    }
+
+    pub fn distance(&self, point: Vec2) -> f32 {
+        self.closest_point(point).distance(point)
+    }
+
+    // The crossing point, or None for parallel segments even when they
+    // overlap
+    pub fn intersection(&self, other: &Segment) -> Option<Vec2> {
+        let t = Ray::new(self.start, self.direction()).cast_segment(other)?;
+        (t <= 1.0).then(|| self.at(t))
+    }
+
+    pub fn overlaps_rect(&self, rect: &Rect) -> bool {
+        Ray::new(self.start, self.direction()).cast_rect(rect).is_some_and(|t| t <= 1.0)
+    }
+
+    pub fn transform(&self, matrix: &Mat4) -> Segment {
+        Segment { start: transform_point(matrix, self.start), end: transform_point(matrix, self.end) }
+    }
+}
+
+impl Ray {
+    pub fn new(origin: Vec2, direction: Vec2) -> Ray {
+        Ray { origin, direction }
+    }
+
+    pub fn at(&self, t: f32) -> Vec2 {
+        self.origin + self.direction * t
+    }
+
+    // The first hit in front of the origin, 0 when the origin is inside
+    pub fn cast_rect(&self, rect: &Rect) -> Option<f32> {
+        let (near, far) = slabs(self.origin, self.direction, rect.min, rect.max)?;
+        (far >= 0.0).then_some(near.max(0.0))
+    }
+
+    pub fn cast_circle(&self, circle: &Circle) -> Option<f32> {
+        let to_origin = self.origin - circle.center;
+        let c = to_origin.length_squared() - circle.radius * circle.radius;
+        if c <= 0.0 {
+            return Some(0.0);
+        }
+        let a = self.direction.length_squared();
+        let b = to_origin.dot(self.direction);
+        let discriminant = b * b - a * c;
+        if a == 0.0 || discriminant < 0.0 {
+            return None;
+        }
+        let t = (-b - discriminant.sqrt()) / a;
+        (t >= 0.0).then_some(t)
+    }
+
+    pub fn cast_segment(&self, segment: &Segment) -> Option<f32> {
+        let edge = segment.direction();
+        let denominator = self.direction.cross(edge);
+        if denominator == 0.0 {
+            return None;
+        }
+        let to_start = segment.start - self.origin;
+        let t = to_start.cross(edge) / denominator;
+        let u = to_start.cross(self.direction) / denominator;
+        (t >= 0.0 && (0.0..=1.0).contains(&u)).then_some(t)
+    }
+
+    pub fn cast_obb(&self, obb: &Obb) -> Option<f32> {
+        // in the box's space the box is -1..1 on both axes, and t carries
+        // over since the mapping is affine
+        let origin = obb.local(self.origin - obb.center)?;
+        let direction = obb.local(self.direction)?;
+        let (near, far) = slabs(origin, direction, Vec2::splat(-1.0), Vec2::splat(1.0))?;
+        (far >= 0.0).then_some(near.max(0.0))
+    }
+
+    pub fn transform(&self, matrix: &Mat4) -> Ray {
+        Ray { origin: transform_point(matrix, self.origin), direction: transform_vector(matrix, self.direction) }
+    }
+}
+
+impl Obb {
+    // Same placement as Mat4::box2d_rot
+    pub fn new(center: Vec2, size: Vec2, rotation: f32) -> Obb {
+        Obb {
+            center,
+            axes: [vec2(size.x / 2.0, 0.0).rotate(rotation), vec2(0.0, size.y / 2.0).rotate(rotation)],
+        }
+    }
+
+    pub fn from_rect(rect: &Rect) -> Obb {
+        let half = rect.size() / 2.0;
+        Obb { center: rect.center(), axes: [vec2(half.x, 0.0), vec2(0.0, half.y)] }
+    }
+
+    // The box a unit square matrix like Mat4::box2d draws
+    pub fn from_matrix(matrix: &Mat4) -> Obb {
+        Obb::from_rect(&Rect::from_center(vec2(0.0, 0.0), vec2(1.0, 1.0))).transform(matrix)
+    }
+
+    pub fn corners(&self) -> [Vec2; 4] {
+        let [a, b] = self.axes;
+        [self.center - a - b, self.center + a - b, self.center + a + b, self.center - a + b]
+    }
+
+    pub fn bounds(&self) -> Rect {
+        let extent = self.axes[0].abs() + self.axes[1].abs();
+        Rect { min: self.center - extent, max: self.center + extent }
+    }
+
+    // `v` in multiples of the axes, None when the box is flat
+    fn local(&self, v: Vec2) -> Option<Vec2> {
+        let [a, b] = self.axes;
+        let determinant = a.cross(b);
+        (determinant != 0.0).then(|| vec2(v.cross(b), a.cross(v)) / determinant)
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.local(point - self.center)
+            .is_some_and(|local| local.x.abs() <= 1.0 && local.y.abs() <= 1.0)
+    }
+
+    // Half the box's width when projected onto `axis`, times its length
+    fn radius_along(&self, axis: Vec2) -> f32 {
+        self.axes[0].dot(axis).abs() + self.axes[1].dot(axis).abs()
+    }
+
+    // Separating axis test on the normals of both boxes' sides
+    pub fn overlaps(&self, other: &Obb) -> bool {
+        let offset = other.center - self.center;
+        self.axes.iter().chain(&other.axes)
+            .map(|axis| axis.perp())
+            .all(|normal| offset.dot(normal).abs() <= self.radius_along(normal) + other.radius_along(normal))
+    }
+
+    pub fn overlaps_rect(&self, rect: &Rect) -> bool {
+        self.overlaps(&Obb::from_rect(rect))
+    }
+
+    pub fn overlaps_circle(&self, circle: &Circle) -> bool {
+        if self.contains(circle.center) {
+            return true;
+        }
+        let corners = self.corners();
+        (0..4).any(|i| Segment::new(corners[i], corners[(i + 1) % 4]).distance(circle.center) <= circle.radius)
+    }
+
+    pub fn transform(&self, matrix: &Mat4) -> Obb {
+        Obb {
+            center: transform_point(matrix, self.center),
+            axes: self.axes.map(|axis| transform_vector(matrix, axis)),
+        }
+    }
+}
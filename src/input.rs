@@ -0,0 +1,166 @@
+use std::collections::HashSet;
+
+use winit::{
+    event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
+    keyboard::{ModifiersState, PhysicalKey},
+};
+
+pub use winit::keyboard::KeyCode;
+
+use crate::mat::{vec2, Vec2};
+
+// we're just assuming a "line" of scrolling is about 32 px
+pub const SCROLL_LINE_PIXELS: f32 = 32.0;
+
+// Keyboard and mouse state kept up to date by win::App. Edges (pressed and
+// released) and the scroll delta cover everything since the previous frame
+// was drawn.
+pub struct InputState {
+    key_down: HashSet<KeyCode>,
+    key_pos_edge: HashSet<KeyCode>,
+    key_neg_edge: HashSet<KeyCode>,
+    mouse_down: HashSet<MouseButton>,
+    mouse_pos_edge: HashSet<MouseButton>,
+    mouse_neg_edge: HashSet<MouseButton>,
+    mouse_position: Vec2,
+    mouse_wheel: Vec2,
+    modifiers: ModifiersState,
+    scale_factor: f64,
+}
+
+impl InputState {
+    pub fn new(scale_factor: f64) -> InputState {
+        InputState {
+            key_down: HashSet::new(),
+            key_pos_edge: HashSet::new(),
+            key_neg_edge: HashSet::new(),
+            mouse_down: HashSet::new(),
+            mouse_pos_edge: HashSet::new(),
+            mouse_neg_edge: HashSet::new(),
+            mouse_position: vec2(0.0, 0.0),
+            mouse_wheel: vec2(0.0, 0.0),
+            modifiers: ModifiersState::empty(),
+            scale_factor,
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput {
+                event: KeyEvent {
+                    physical_key: PhysicalKey::Code(key),
+                    state,
+                    repeat,
+                    ..
+                },
+                ..
+            } => self.key(*key, *state, *repeat),
+            WindowEvent::MouseInput { state, button, .. } => self.mouse_button(*button, *state),
+            WindowEvent::CursorMoved { position, .. } => {
+                self.mouse_position = vec2(position.x as f32, position.y as f32);
+            },
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (dx, dy) = match delta {
+                    MouseScrollDelta::LineDelta(dx, dy) => (dx * SCROLL_LINE_PIXELS, dy * SCROLL_LINE_PIXELS),
+                    MouseScrollDelta::PixelDelta(position) => (position.x as f32, position.y as f32),
+                };
+                self.mouse_wheel.x += dx;
+                self.mouse_wheel.y += dy;
+            },
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.state(),
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => self.scale_factor = *scale_factor,
+            // nothing gets released while unfocused, so forget everything held
+            WindowEvent::Focused(false) => {
+                self.key_neg_edge.extend(self.key_down.drain());
+                self.mouse_neg_edge.extend(self.mouse_down.drain());
+                self.modifiers = ModifiersState::empty();
+            },
+            _ => (),
+        }
+    }
+
+    pub fn key(&mut self, key: KeyCode, state: ElementState, repeat: bool) {
+        match state {
+            ElementState::Pressed => {
+                if !repeat && self.key_down.insert(key) {
+                    self.key_pos_edge.insert(key);
+                }
+            },
+            ElementState::Released => {
+                if self.key_down.remove(&key) {
+                    self.key_neg_edge.insert(key);
+                }
+            },
+        }
+    }
+
+    pub fn mouse_button(&mut self, button: MouseButton, state: ElementState) {
+        match state {
+            ElementState::Pressed => {
+                if self.mouse_down.insert(button) {
+                    self.mouse_pos_edge.insert(button);
+                }
+            },
+            ElementState::Released => {
+                if self.mouse_down.remove(&button) {
+                    self.mouse_neg_edge.insert(button);
+                }
+            },
+        }
+    }
+
+    // Clears per-frame state, called by win::App after each frame
+    pub fn end_frame(&mut self) {
+        self.key_pos_edge.clear();
+        self.key_neg_edge.clear();
+        self.mouse_pos_edge.clear();
+        self.mouse_neg_edge.clear();
+        self.mouse_wheel = vec2(0.0, 0.0);
+    }
+
+    pub fn key_down(&self, key: KeyCode) -> bool {
+        self.key_down.contains(&key)
+    }
+    pub fn key_pressed(&self, key: KeyCode) -> bool {
+        self.key_pos_edge.contains(&key)
+    }
+    pub fn key_released(&self, key: KeyCode) -> bool {
+        self.key_neg_edge.contains(&key)
+    }
+    pub fn keys_down(&self) -> impl Iterator<Item = &KeyCode> {
+        self.key_down.iter()
+    }
+
+    pub fn mouse_down(&self, button: MouseButton) -> bool {
+        self.mouse_down.contains(&button)
+    }
+    pub fn mouse_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_pos_edge.contains(&button)
+    }
+    pub fn mouse_released(&self, button: MouseButton) -> bool {
+        self.mouse_neg_edge.contains(&button)
+    }
+
+    // in physical pixels from the top left of the window
+    pub fn mouse_position(&self) -> Vec2 {
+        self.mouse_position
+    }
+    pub fn mouse_position_logical(&self) -> Vec2 {
+        let scale = self.scale_factor as f32;
+        self.mouse_position / scale
+    }
+
+    // physical pixels per logical pixel, e.g. 2 on most high DPI screens
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    // in physical pixels, summed over the frame
+    pub fn scroll(&self) -> Vec2 {
+        self.mouse_wheel
+    }
+
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+}
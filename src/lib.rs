@@ -6,7 +6,21 @@ pub mod client;
 pub mod simple;
 pub mod square;
 pub mod mat;
+pub mod geom;
 pub mod rrs;
 pub mod textured;
+pub mod text;
 pub mod texture;
+pub mod target;
+pub mod frame;
+pub mod timestep;
+pub mod input;
+pub mod action;
+pub mod dispatch;
+pub mod console;
+pub mod postprocess;
+pub mod pixel;
+pub mod projection;
+pub mod animation;
+pub mod atlas;
 pub mod util;
@@ -7,8 +7,17 @@ pub struct Mat4 {
 }
 unsafe impl bytemuck::Zeroable for Mat4 {}
 
+// A 2D affine transform, cheaper than a Mat4 for transforming points on the
+// CPU. Column major like Mat4, the last column is the translation.
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Debug)]
+pub struct Mat3 {
+    pub data: [[f32; 3]; 3],
+}
+unsafe impl bytemuck::Zeroable for Mat3 {}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Debug, PartialEq)]
 pub struct Vec2 {
     pub data: [f32; 2],
 }
@@ -22,7 +31,7 @@ unsafe impl bytemuck::Zeroable for Vec2 {}
 unsafe impl bytemuck::Zeroable for DVec2 {}
 
 #[repr(C)]
-#[derive(Clone, Copy, Pod, Debug)]
+#[derive(Clone, Copy, Pod, Debug, PartialEq)]
 pub struct Vec3 {
     pub data: [f32; 3],
 }
@@ -37,7 +46,7 @@ unsafe impl bytemuck::Zeroable for Vec3 {}
 unsafe impl bytemuck::Zeroable for DVec3 {}
 
 #[repr(C)]
-#[derive(Clone, Copy, Pod, Debug)]
+#[derive(Clone, Copy, Pod, Debug, PartialEq)]
 pub struct Vec4 {
     pub data: [f32; 4],
 }
@@ -62,6 +71,8 @@ impl Mat4 {
         }
     }
 
+    // One unit per pixel of `size`, y down from the top left. See
+    // projection::Projection to keep coordinates fixed across window sizes.
     pub fn ortho(size: winit::dpi::PhysicalSize<u32>) -> Self {
         let w: f32 = size.width as f32;
         let h: f32 = size.height as f32;
@@ -73,6 +84,41 @@ impl Mat4 {
         }
     }
 
+    // Like ortho but one unit per logical pixel, so things keep their size
+    // on high DPI screens, with RenderContext::scale_factor
+    pub fn ortho_logical(size: winit::dpi::PhysicalSize<u32>, scale_factor: f64) -> Self {
+        let size = size.to_logical::<f32>(scale_factor);
+        Self::ortho_bounds(0.0, size.width, size.height, 0.0, 0.0, 1.0)
+    }
+
+    // Maps x from left..right and y from bottom..top to -1..1, and z from
+    // near..far to 0..1 like wgpu expects. Passing top < bottom flips y, so
+    // ortho_bounds(0, w, h, 0, 0, 1) is the same as ortho.
+    pub fn ortho_bounds(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self {
+        let w = right - left;
+        let h = top - bottom;
+        let d = far - near;
+        Self {
+            data: [[ 2.0 / w,                 0.0,                     0.0,         0.0],
+                   [ 0.0,                     2.0 / h,                 0.0,         0.0],
+                   [ 0.0,                     0.0,                     1.0 / d,     0.0],
+                   [-(right + left) / w,     -(top + bottom) / h,     -near / d,    1.0]]
+        }
+    }
+
+    // Right handed view matrix looking from `eye` towards `target`
+    pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        let f = (target - eye).normalize();
+        let s = f.cross(up).normalize();
+        let u = s.cross(f);
+        Self {
+            data: [[s.x,          u.x,         -f.x,          0.0],
+                   [s.y,          u.y,         -f.y,          0.0],
+                   [s.z,          u.z,         -f.z,          0.0],
+                   [-s.dot(eye), -u.dot(eye),   f.dot(eye),   1.0]]
+        }
+    }
+
     pub fn box2d(pos: Vec2, size: Vec2) -> Self {
         return Self::translate2d(pos) * Self::scale2d(size);
     }
@@ -131,6 +177,65 @@ impl Mat4 {
                    [0.0, 0.0, 0.0, 1.0]]
         }
     }
+
+    pub fn transpose(&self) -> Self {
+        let m = &self.data;
+        Self {
+            data: std::array::from_fn(|c| std::array::from_fn(|r| m[r][c]))
+        }
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let (a, b) = self.cofactor_pairs();
+        a[0] * b[5] - a[1] * b[4] + a[2] * b[3] + a[3] * b[2] - a[4] * b[1] + a[5] * b[0]
+    }
+
+    // None when the matrix is singular, like a scale by zero
+    pub fn inverse(&self) -> Option<Self> {
+        let m = &self.data;
+        let (a, b) = self.cofactor_pairs();
+        let det = a[0] * b[5] - a[1] * b[4] + a[2] * b[3] + a[3] * b[2] - a[4] * b[1] + a[5] * b[0];
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let inv = 1.0 / det;
+        let data = [[( m[1][1] * b[5] - m[1][2] * b[4] + m[1][3] * b[3]) * inv,
+                     (-m[0][1] * b[5] + m[0][2] * b[4] - m[0][3] * b[3]) * inv,
+                     ( m[3][1] * a[5] - m[3][2] * a[4] + m[3][3] * a[3]) * inv,
+                     (-m[2][1] * a[5] + m[2][2] * a[4] - m[2][3] * a[3]) * inv],
+                    [(-m[1][0] * b[5] + m[1][2] * b[2] - m[1][3] * b[1]) * inv,
+                     ( m[0][0] * b[5] - m[0][2] * b[2] + m[0][3] * b[1]) * inv,
+                     (-m[3][0] * a[5] + m[3][2] * a[2] - m[3][3] * a[1]) * inv,
+                     ( m[2][0] * a[5] - m[2][2] * a[2] + m[2][3] * a[1]) * inv],
+                    [( m[1][0] * b[4] - m[1][1] * b[2] + m[1][3] * b[0]) * inv,
+                     (-m[0][0] * b[4] + m[0][1] * b[2] - m[0][3] * b[0]) * inv,
+                     ( m[3][0] * a[4] - m[3][1] * a[2] + m[3][3] * a[0]) * inv,
+                     (-m[2][0] * a[4] + m[2][1] * a[2] - m[2][3] * a[0]) * inv],
+                    [(-m[1][0] * b[3] + m[1][1] * b[1] - m[1][2] * b[0]) * inv,
+                     ( m[0][0] * b[3] - m[0][1] * b[1] + m[0][2] * b[0]) * inv,
+                     (-m[3][0] * a[3] + m[3][1] * a[1] - m[3][2] * a[0]) * inv,
+                     ( m[2][0] * a[3] - m[2][1] * a[1] + m[2][2] * a[0]) * inv]];
+        Some(Self { data })
+    }
+
+    // 2x2 determinants of the first two and last two columns, shared by
+    // determinant and inverse
+    fn cofactor_pairs(&self) -> ([f32; 6], [f32; 6]) {
+        let m = &self.data;
+        let a = [m[0][0] * m[1][1] - m[1][0] * m[0][1],
+                 m[0][0] * m[1][2] - m[1][0] * m[0][2],
+                 m[0][0] * m[1][3] - m[1][0] * m[0][3],
+                 m[0][1] * m[1][2] - m[1][1] * m[0][2],
+                 m[0][1] * m[1][3] - m[1][1] * m[0][3],
+                 m[0][2] * m[1][3] - m[1][2] * m[0][3]];
+        let b = [m[2][0] * m[3][1] - m[3][0] * m[2][1],
+                 m[2][0] * m[3][2] - m[3][0] * m[2][2],
+                 m[2][0] * m[3][3] - m[3][0] * m[2][3],
+                 m[2][1] * m[3][2] - m[3][1] * m[2][2],
+                 m[2][1] * m[3][3] - m[3][1] * m[2][3],
+                 m[2][2] * m[3][3] - m[3][2] * m[2][3]];
+        (a, b)
+    }
 }
 
 impl std::ops::Mul for &Mat4 {
@@ -171,6 +276,137 @@ impl std::ops::Mul for Mat4 {
     }
 }
 
+impl Mat3 {
+    pub fn identity() -> Self {
+        Self {
+            data: [[1.0, 0.0, 0.0],
+                   [0.0, 1.0, 0.0],
+                   [0.0, 0.0, 1.0]]
+        }
+    }
+
+    pub fn box2d(pos: Vec2, size: Vec2) -> Self {
+        Self::translate2d(pos) * Self::scale2d(size)
+    }
+
+    pub fn box2d_rot(pos: Vec2, size: Vec2, rotation: f32) -> Self {
+        Self::translate2d(pos) * Self::rotate2d(rotation) * Self::scale2d(size)
+    }
+
+    pub fn scale2d(scale: Vec2) -> Self {
+        let s = scale;
+        Self {
+            data: [[s.x, 0.0, 0.0],
+                   [0.0, s.y, 0.0],
+                   [0.0, 0.0, 1.0]]
+        }
+    }
+
+    pub fn translate2d(movement: Vec2) -> Self {
+        let v = movement;
+        Self {
+            data: [[1.0, 0.0, 0.0],
+                   [0.0, 1.0, 0.0],
+                   [v.x, v.y, 1.0]]
+        }
+    }
+
+    // same direction as Mat4::rotate2d
+    pub fn rotate2d(angle: f32) -> Self {
+        let c_t = f32::cos(angle);
+        let s_t = f32::sin(angle);
+        Self {
+            data: [[c_t,-s_t, 0.0],
+                   [s_t, c_t, 0.0],
+                   [0.0, 0.0, 1.0]]
+        }
+    }
+
+    pub fn transpose(&self) -> Self {
+        let m = &self.data;
+        Self {
+            data: std::array::from_fn(|c| std::array::from_fn(|r| m[r][c]))
+        }
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let m = &self.data;
+        m[0][0] * (m[1][1] * m[2][2] - m[2][1] * m[1][2])
+            - m[1][0] * (m[0][1] * m[2][2] - m[2][1] * m[0][2])
+            + m[2][0] * (m[0][1] * m[1][2] - m[1][1] * m[0][2])
+    }
+
+    // None when the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        let m = &self.data;
+        let det = self.determinant();
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let inv = 1.0 / det;
+        // transposed cofactors
+        let data = [[(m[1][1] * m[2][2] - m[2][1] * m[1][2]) * inv,
+                     (m[2][1] * m[0][2] - m[0][1] * m[2][2]) * inv,
+                     (m[0][1] * m[1][2] - m[1][1] * m[0][2]) * inv],
+                    [(m[2][0] * m[1][2] - m[1][0] * m[2][2]) * inv,
+                     (m[0][0] * m[2][2] - m[2][0] * m[0][2]) * inv,
+                     (m[1][0] * m[0][2] - m[0][0] * m[1][2]) * inv],
+                    [(m[1][0] * m[2][1] - m[2][0] * m[1][1]) * inv,
+                     (m[2][0] * m[0][1] - m[0][0] * m[2][1]) * inv,
+                     (m[0][0] * m[1][1] - m[1][0] * m[0][1]) * inv]];
+        Some(Self { data })
+    }
+
+    pub fn transform_point(&self, p: Vec2) -> Vec2 {
+        let m = &self.data;
+        vec2(m[0][0] * p.x + m[1][0] * p.y + m[2][0],
+             m[0][1] * p.x + m[1][1] * p.y + m[2][1])
+    }
+
+    // ignores the translation
+    pub fn transform_vector(&self, v: Vec2) -> Vec2 {
+        let m = &self.data;
+        vec2(m[0][0] * v.x + m[1][0] * v.y,
+             m[0][1] * v.x + m[1][1] * v.y)
+    }
+
+    pub fn to_mat4(&self) -> Mat4 {
+        let m = &self.data;
+        Mat4 {
+            data: [[m[0][0], m[0][1], 0.0, m[0][2]],
+                   [m[1][0], m[1][1], 0.0, m[1][2]],
+                   [0.0,     0.0,     1.0, 0.0],
+                   [m[2][0], m[2][1], 0.0, m[2][2]]]
+        }
+    }
+}
+
+impl std::ops::Mul for Mat3 {
+    type Output = Mat3;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let a = &self.data;
+        let b = &rhs.data;
+        Mat3 {
+            data: std::array::from_fn(|c| std::array::from_fn(|r|
+                a[0][r] * b[c][0] + a[1][r] * b[c][1] + a[2][r] * b[c][2]))
+        }
+    }
+}
+impl std::ops::Mul for &Mat3 {
+    type Output = Mat3;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        *self * *rhs
+    }
+}
+
+impl From<Mat3> for Mat4 {
+    fn from(m: Mat3) -> Mat4 {
+        m.to_mat4()
+    }
+}
+
 impl std::ops::Rem<&Vec4> for &Mat4 {
     type Output = Vec4;
 
@@ -255,6 +491,187 @@ pub fn vec4e(v: Vec3, d: f32) -> Vec4 {
     }
 }
 
+// Component-wise arithmetic, shared by every vector size
+macro_rules! vec_ops {
+    ($vec:ident, $n:literal) => {
+        impl $vec {
+            pub fn splat(v: f32) -> $vec {
+                $vec { data: [v; $n] }
+            }
+
+            pub fn dot(self, other: $vec) -> f32 {
+                self.data.iter().zip(other.data).map(|(a, b)| a * b).sum()
+            }
+
+            pub fn length_squared(self) -> f32 {
+                self.dot(self)
+            }
+
+            pub fn length(self) -> f32 {
+                self.length_squared().sqrt()
+            }
+
+            pub fn distance(self, other: $vec) -> f32 {
+                (self - other).length()
+            }
+
+            // zero stays zero instead of turning into NaN
+            pub fn normalize(self) -> $vec {
+                let length = self.length();
+                if length == 0.0 {
+                    self
+                } else {
+                    self / length
+                }
+            }
+
+            pub fn lerp(self, other: $vec, t: f32) -> $vec {
+                self + (other - self) * t
+            }
+
+            pub fn min(self, other: $vec) -> $vec {
+                self.zip(other, f32::min)
+            }
+
+            pub fn max(self, other: $vec) -> $vec {
+                self.zip(other, f32::max)
+            }
+
+            pub fn abs(self) -> $vec {
+                self.map(f32::abs)
+            }
+
+            pub fn map(self, f: impl Fn(f32) -> f32) -> $vec {
+                $vec { data: self.data.map(f) }
+            }
+
+            pub fn zip(self, other: $vec, f: impl Fn(f32, f32) -> f32) -> $vec {
+                $vec { data: std::array::from_fn(|i| f(self.data[i], other.data[i])) }
+            }
+        }
+
+        impl std::ops::Add for $vec {
+            type Output = $vec;
+            fn add(self, rhs: $vec) -> $vec {
+                self.zip(rhs, |a, b| a + b)
+            }
+        }
+        impl std::ops::Sub for $vec {
+            type Output = $vec;
+            fn sub(self, rhs: $vec) -> $vec {
+                self.zip(rhs, |a, b| a - b)
+            }
+        }
+        impl std::ops::Mul for $vec {
+            type Output = $vec;
+            fn mul(self, rhs: $vec) -> $vec {
+                self.zip(rhs, |a, b| a * b)
+            }
+        }
+        impl std::ops::Div for $vec {
+            type Output = $vec;
+            fn div(self, rhs: $vec) -> $vec {
+                self.zip(rhs, |a, b| a / b)
+            }
+        }
+        impl std::ops::Mul<f32> for $vec {
+            type Output = $vec;
+            fn mul(self, rhs: f32) -> $vec {
+                self.map(|a| a * rhs)
+            }
+        }
+        impl std::ops::Mul<$vec> for f32 {
+            type Output = $vec;
+            fn mul(self, rhs: $vec) -> $vec {
+                rhs * self
+            }
+        }
+        impl std::ops::Div<f32> for $vec {
+            type Output = $vec;
+            fn div(self, rhs: f32) -> $vec {
+                self.map(|a| a / rhs)
+            }
+        }
+        impl std::ops::Neg for $vec {
+            type Output = $vec;
+            fn neg(self) -> $vec {
+                self.map(|a| -a)
+            }
+        }
+        impl std::ops::AddAssign for $vec {
+            fn add_assign(&mut self, rhs: $vec) {
+                *self = *self + rhs;
+            }
+        }
+        impl std::ops::SubAssign for $vec {
+            fn sub_assign(&mut self, rhs: $vec) {
+                *self = *self - rhs;
+            }
+        }
+        impl std::ops::MulAssign<f32> for $vec {
+            fn mul_assign(&mut self, rhs: f32) {
+                *self = *self * rhs;
+            }
+        }
+        impl std::ops::DivAssign<f32> for $vec {
+            fn div_assign(&mut self, rhs: f32) {
+                *self = *self / rhs;
+            }
+        }
+        impl From<[f32; $n]> for $vec {
+            fn from(data: [f32; $n]) -> $vec {
+                $vec { data }
+            }
+        }
+    };
+}
+
+vec_ops!(Vec2, 2);
+vec_ops!(Vec3, 3);
+vec_ops!(Vec4, 4);
+
+impl Vec2 {
+    // Angles follow Mat4::rotate2d, so positive is counterclockwise on a y
+    // down screen
+
+    // z of the 3D cross product, the sign says which side of self `other` is
+    pub fn cross(self, other: Vec2) -> f32 {
+        self.x * other.y - self.y * other.x
+    }
+
+    // rotated a quarter turn, same as rotate(PI / 2)
+    pub fn perp(self) -> Vec2 {
+        vec2(self.y, -self.x)
+    }
+
+    // angle from the x axis, in -pi..pi
+    pub fn angle(self) -> f32 {
+        (-self.y).atan2(self.x)
+    }
+
+    // signed angle that rotates self onto other's direction
+    pub fn angle_to(self, other: Vec2) -> f32 {
+        other.cross(self).atan2(self.dot(other))
+    }
+
+    pub fn rotate(self, angle: f32) -> Vec2 {
+        let (s, c) = angle.sin_cos();
+        vec2(self.x * c + self.y * s, self.y * c - self.x * s)
+    }
+
+    pub fn from_angle(angle: f32) -> Vec2 {
+        vec2(angle.cos(), -angle.sin())
+    }
+}
+
+impl Vec3 {
+    pub fn cross(self, other: Vec3) -> Vec3 {
+        vec3(self.y * other.z - self.z * other.y,
+             self.z * other.x - self.x * other.z,
+             self.x * other.y - self.y * other.x)
+    }
+}
+
 impl std::ops::Deref for Vec2 {
     type Target = DVec2;
 
@@ -433,21 +850,6 @@ impl Into<[u8; 64]> for Mat4 {
 //     }
 // }
 // 
-// pub struct BoundingBox {
-//     pub center: Vector2<f32>,
-//     pub scale: Vector2<f32>,
-// }
-// 
-// impl BoundingBox {
-//     pub fn new(center: Vector2<f32>, scale: Vector2<f32>) -> Self {
-//         Self { center, scale }
-//     }
-//     pub fn contains(&self, point: &Vector2<f32>) -> bool {
-//         self.center.x - self.scale.x / 2.0 <= point.x && point.x <= self.center.x + self.scale.x / 2.0 &&
-//         self.center.y - self.scale.y / 2.0 <= point.y && point.y <= self.center.y + self.scale.y / 2.0
-//     }
-// }
-// 
 // pub fn world_to_world_screen_space(v: Vector3<f32>) -> Vector2<f32> {
 //     vec2(v.x, v.y - v.z)
 // }
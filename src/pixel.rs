@@ -0,0 +1,223 @@
+use std::{borrow::Cow, str};
+
+use wgpu::util::DeviceExt;
+use winit::dpi::PhysicalSize;
+
+use crate::{
+    geom::Rect,
+    mat::{vec2, vec4, Mat4, Vec2, Vec4},
+    rrs::Settings,
+    target::RenderTarget,
+    win::RenderContext,
+};
+
+// Pixel perfect rendering for pixel art. A camera renders into `target()` at
+// a fixed virtual resolution with `settings()`, which snaps sprites to whole
+// texels, then `draw` upscales the result into another pass by the largest
+// whole number that fits, centered with bars around it. Anything drawn in
+// that pass afterwards stays at the window's resolution, so UI can be layered
+// on top of a pixel perfect world.
+//
+// MSAA has to be off, WindowConfig::sample_count of 1. The renderers' pipelines
+// all use the context's sample count, so the target would be multisampled too
+// and sprite edges would blend into their neighbours when it's resolved.
+// `init` returns None otherwise.
+pub struct PixelPerfect {
+    target: RenderTarget,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+    uniform_buf: wgpu::Buffer,
+}
+
+// The largest whole number scale that fits `resolution` in `window`. Never
+// less than 1, so small windows crop the image instead of shrinking it.
+pub fn integer_scale(resolution: PhysicalSize<u32>, window: PhysicalSize<u32>) -> u32 {
+    (window.width / resolution.width.max(1))
+        .min(window.height / resolution.height.max(1))
+        .max(1)
+}
+
+// Where the upscaled image goes in the window, in pixels from the top left.
+// The offset is rounded so texels stay aligned to the window's pixels.
+pub fn letterbox(resolution: PhysicalSize<u32>, window: PhysicalSize<u32>) -> Rect {
+    let scale = integer_scale(resolution, window) as f32;
+    let size = vec2(resolution.width as f32 * scale, resolution.height as f32 * scale);
+    let window = vec2(window.width as f32, window.height as f32);
+    Rect::from_pos_size(((window - size) / 2.0).map(f32::floor), size)
+}
+
+// Converts a position in the window, like InputState::mouse_position, to
+// pixels of the virtual resolution. None over the bars.
+pub fn window_to_virtual(resolution: PhysicalSize<u32>, window: PhysicalSize<u32>, position: Vec2) -> Option<Vec2> {
+    let area = letterbox(resolution, window);
+    area.contains(position).then(|| (position - area.min) / integer_scale(resolution, window) as f32)
+}
+
+// The letterbox cut down to the window, since viewports can't leave the
+// attachment, along with the part of the target that's still visible as
+// uv offset and size
+fn crop(resolution: PhysicalSize<u32>, window: PhysicalSize<u32>) -> Option<(Rect, Vec4)> {
+    let area = letterbox(resolution, window);
+    let visible = area.intersection(&Rect::new(vec2(0.0, 0.0), vec2(window.width as f32, window.height as f32)))?;
+    if visible.area() <= 0.0 {
+        return None;
+    }
+    let offset = (visible.min - area.min) / area.size();
+    let size = visible.size() / area.size();
+    Some((visible, vec4(offset.x, offset.y, size.x, size.y)))
+}
+
+impl PixelPerfect {
+    pub fn init(rc: &mut RenderContext, resolution: PhysicalSize<u32>) -> Option<PixelPerfect> {
+        if rc.sample_count != 1 {
+            return None;
+        }
+        let bind_group_layout = rc.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Pixel perfect bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }, wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            }, wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = rc.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Pixel perfect pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let prelude = str::from_utf8(include_bytes!(env!("POSTPROCESS_SHADER"))).unwrap();
+        let source = str::from_utf8(include_bytes!(env!("PIXEL_SHADER"))).unwrap();
+        let shader = rc.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Pixel perfect"),
+            source: wgpu::ShaderSource::Wgsl(Cow::from(format!("{}\n{}", prelude, source))),
+        });
+        // drawn inside ordinary passes, so it matches the renderers' targets
+        let pipeline = rc.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pixel perfect"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            primitive: Default::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: rc.sample_count,
+                ..Default::default()
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(rc.surface_format.into())],
+            }),
+            multiview: None,
+            cache: None,
+        });
+        let uniform_buf = rc.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Pixel perfect"),
+            contents: bytemuck::bytes_of(&vec4(0.0, 0.0, 1.0, 1.0)),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+        });
+        let target = RenderTarget::init(rc, resolution);
+        let bind_group = Self::create_bind_group(rc, &bind_group_layout, &target, &uniform_buf);
+        Some(PixelPerfect {
+            target,
+            bind_group_layout,
+            bind_group,
+            pipeline,
+            uniform_buf,
+        })
+    }
+
+    fn create_bind_group(
+        rc: &mut RenderContext,
+        layout: &wgpu::BindGroupLayout,
+        target: &RenderTarget,
+        uniform_buf: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        // RenderTarget's sampler is already nearest
+        rc.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(target.view()),
+            }, wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&target.info.sampler),
+            }, wgpu::BindGroupEntry {
+                binding: 2,
+                resource: uniform_buf.as_entire_binding(),
+            }],
+        })
+    }
+
+    pub fn resolution(&self) -> PhysicalSize<u32> {
+        self.target.size
+    }
+
+    pub fn set_resolution(&mut self, rc: &mut RenderContext, resolution: PhysicalSize<u32>) {
+        self.target = RenderTarget::init(rc, resolution);
+        self.bind_group = Self::create_bind_group(rc, &self.bind_group_layout, &self.target, &self.uniform_buf);
+    }
+
+    // The camera renders into this, e.g. with PassContext::begin_on
+    pub fn target(&self) -> &RenderTarget {
+        &self.target
+    }
+
+    // Settings for drawing into the target with `projection`, which maps
+    // the world to the virtual resolution however the camera likes
+    pub fn settings(&self, projection: Mat4) -> Settings {
+        Settings {
+            projection,
+            cull: true,
+            snap: Some(self.resolution()),
+        }
+    }
+
+    // One world unit per virtual pixel, with the origin at the top left
+    pub fn projection(&self) -> Mat4 {
+        Mat4::ortho(self.resolution())
+    }
+
+    // Draws the target upscaled into `rpass`, whose attachment is `window`
+    // sized. Only the letterboxed area is touched, so the pass's clear color
+    // shows in the bars.
+    pub fn draw(&self, rc: &mut RenderContext, rpass: &mut wgpu::RenderPass, window: PhysicalSize<u32>) {
+        let Some((viewport, uv_rect)) = crop(self.resolution(), window) else { return };
+        rc.queue.write_buffer(&self.uniform_buf, 0, bytemuck::bytes_of(&uv_rect));
+        rpass.set_viewport(viewport.min.x, viewport.min.y, viewport.width(), viewport.height(), 0.0, 1.0);
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.draw(0..3, 0..1);
+        rpass.set_viewport(0.0, 0.0, window.width as f32, window.height as f32, 0.0, 1.0);
+    }
+
+    pub fn window_to_virtual(&self, window: PhysicalSize<u32>, position: Vec2) -> Option<Vec2> {
+        window_to_virtual(self.resolution(), window, position)
+    }
+}
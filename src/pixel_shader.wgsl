@@ -0,0 +1,14 @@
+// Appended to postprocess_shader.wgsl. Samples the low resolution target
+// with its nearest sampler, so every texel becomes a block of whole pixels
+// as long as the viewport is a whole multiple of the target's size.
+
+struct Params {
+    // offset and size of the part of the target that's on screen, in uvs
+    uv_rect: vec4<f32>,
+};
+@group(0) @binding(2) var<uniform> params: Params;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(source_texture, source_sampler, params.uv_rect.xy + in.uv * params.uv_rect.zw);
+}
@@ -0,0 +1,353 @@
+use std::{borrow::Cow, str};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+use crate::{mat::Vec4, mat::vec4, target::RenderTarget, win::RenderContext};
+
+// Post-processing chain. The scene is rendered into `scene()`, then every
+// enabled effect runs as a fullscreen pass, ping-ponging between two targets,
+// with the last effect writing to the output view.
+//
+// Effect shaders are appended to postprocess_shader.wgsl, which provides
+// `vs_main`, `VertexOutput` and the source texture/sampler at bindings 0 and 1.
+// An effect only declares its `Params` uniform at binding 2 and an `fs_main`.
+pub struct PostProcess {
+    scene: RenderTarget,
+    swap: RenderTarget,
+    sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    copy: Effect,
+    pub effects: Vec<Effect>,
+}
+
+pub struct Effect {
+    pub label: String,
+    pub enabled: bool,
+    pipeline: wgpu::RenderPipeline,
+    uniform_buf: wgpu::Buffer,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable, Debug)]
+pub struct ColorGrade {
+    pub brightness: f32,
+    pub contrast: f32,
+    pub saturation: f32,
+    pub gamma: f32,
+    pub tint: Vec4,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable, Debug)]
+pub struct Vignette {
+    pub color: Vec4,
+    pub intensity: f32,
+    pub radius: f32,
+    pub softness: f32,
+    pub _pad: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable, Debug)]
+pub struct Bloom {
+    pub threshold: f32,
+    pub intensity: f32,
+    pub radius: f32,
+    pub _pad: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable, Debug)]
+pub struct Crt {
+    pub scanline_intensity: f32,
+    pub scanline_count: f32,
+    pub curvature: f32,
+    pub _pad: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable, Debug)]
+pub struct Blur {
+    pub direction: [f32; 2],
+    pub radius: f32,
+    pub _pad: f32,
+}
+
+impl Default for ColorGrade {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+            gamma: 1.0,
+            tint: Vec4::identity(),
+        }
+    }
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self {
+            color: vec4(0.0, 0.0, 0.0, 1.0),
+            intensity: 0.8,
+            radius: 0.45,
+            softness: 0.35,
+            _pad: 0.0,
+        }
+    }
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self {
+            threshold: 0.7,
+            intensity: 1.0,
+            radius: 4.0,
+            _pad: 0.0,
+        }
+    }
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Self {
+            scanline_intensity: 0.25,
+            scanline_count: 240.0,
+            curvature: 0.08,
+            _pad: 0.0,
+        }
+    }
+}
+
+impl Default for Blur {
+    fn default() -> Self {
+        Self {
+            direction: [1.0, 0.0],
+            radius: 1.0,
+            _pad: 0.0,
+        }
+    }
+}
+
+fn load_shader(bytes: &[u8]) -> &str {
+    str::from_utf8(bytes).unwrap()
+}
+
+impl PostProcess {
+    pub fn init(rc: &mut RenderContext, size: winit::dpi::PhysicalSize<u32>) -> PostProcess {
+        let bind_group_layout = rc.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post process bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }, wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            }, wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = rc.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post process pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let sampler = rc.device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let copy = Effect::build(
+            rc,
+            &pipeline_layout,
+            "copy",
+            load_shader(include_bytes!(env!("POSTPROCESS_COPY_SHADER"))),
+            bytemuck::bytes_of(&Vec4::zero()),
+        );
+        PostProcess {
+            scene: RenderTarget::init(rc, size),
+            swap: RenderTarget::init(rc, size),
+            sampler,
+            bind_group_layout,
+            pipeline_layout,
+            copy,
+            effects: vec![],
+        }
+    }
+
+    pub fn resize(&mut self, rc: &mut RenderContext, size: winit::dpi::PhysicalSize<u32>) {
+        self.scene = RenderTarget::init(rc, size);
+        self.swap = RenderTarget::init(rc, size);
+    }
+
+    // The target the main pass should render into before `apply`.
+    pub fn scene(&self) -> &RenderTarget {
+        &self.scene
+    }
+
+    pub fn add(&mut self, effect: Effect) -> usize {
+        self.effects.push(effect);
+        self.effects.len() - 1
+    }
+
+    pub fn effect(
+        &self,
+        rc: &mut RenderContext,
+        label: &str,
+        source: &str,
+        params: &impl Pod,
+    ) -> Effect {
+        Effect::build(rc, &self.pipeline_layout, label, source, bytemuck::bytes_of(params))
+    }
+
+    pub fn color_grade(&self, rc: &mut RenderContext, params: ColorGrade) -> Effect {
+        self.effect(rc, "color grade", load_shader(include_bytes!(env!("POSTPROCESS_GRADE_SHADER"))), &params)
+    }
+
+    pub fn vignette(&self, rc: &mut RenderContext, params: Vignette) -> Effect {
+        self.effect(rc, "vignette", load_shader(include_bytes!(env!("POSTPROCESS_VIGNETTE_SHADER"))), &params)
+    }
+
+    pub fn bloom(&self, rc: &mut RenderContext, params: Bloom) -> Effect {
+        self.effect(rc, "bloom", load_shader(include_bytes!(env!("POSTPROCESS_BLOOM_SHADER"))), &params)
+    }
+
+    pub fn crt(&self, rc: &mut RenderContext, params: Crt) -> Effect {
+        self.effect(rc, "crt", load_shader(include_bytes!(env!("POSTPROCESS_CRT_SHADER"))), &params)
+    }
+
+    pub fn blur(&self, rc: &mut RenderContext, params: Blur) -> Effect {
+        self.effect(rc, "blur", load_shader(include_bytes!(env!("POSTPROCESS_BLUR_SHADER"))), &params)
+    }
+
+    // Runs the effect chain on the scene target and writes the result to `output`.
+    pub fn apply(&self, rc: &mut RenderContext, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        let enabled: Vec<&Effect> = self.effects.iter().filter(|effect| effect.enabled).collect();
+        let chain = if enabled.is_empty() { vec![&self.copy] } else { enabled };
+        let mut source = &self.scene;
+        let mut spare = &self.swap;
+        for (index, effect) in chain.iter().enumerate() {
+            let last = index + 1 == chain.len();
+            let target = if last { output } else { spare.view() };
+            self.run(rc, encoder, effect, source.view(), target);
+            if !last {
+                std::mem::swap(&mut source, &mut spare);
+            }
+        }
+    }
+
+    fn run(
+        &self,
+        rc: &mut RenderContext,
+        encoder: &mut wgpu::CommandEncoder,
+        effect: &Effect,
+        source: &wgpu::TextureView,
+        target: &wgpu::TextureView,
+    ) {
+        let bind_group = rc.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(source),
+            }, wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&self.sampler),
+            }, wgpu::BindGroupEntry {
+                binding: 2,
+                resource: effect.uniform_buf.as_entire_binding(),
+            }],
+        });
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(&effect.label),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        rpass.set_pipeline(&effect.pipeline);
+        rpass.set_bind_group(0, &bind_group, &[]);
+        rpass.draw(0..3, 0..1);
+    }
+}
+
+impl Effect {
+    fn build(
+        rc: &mut RenderContext,
+        pipeline_layout: &wgpu::PipelineLayout,
+        label: &str,
+        source: &str,
+        params: &[u8],
+    ) -> Effect {
+        let prelude = load_shader(include_bytes!(env!("POSTPROCESS_SHADER")));
+        let shader = rc.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(Cow::from(format!("{}\n{}", prelude, source))),
+        });
+        let pipeline = rc.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            primitive: Default::default(),
+            depth_stencil: None,
+            multisample: Default::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(rc.surface_format.into())],
+            }),
+            multiview: None,
+            cache: None,
+        });
+        let uniform_buf = rc.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: params,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+        });
+        Effect {
+            label: label.to_string(),
+            enabled: true,
+            pipeline,
+            uniform_buf,
+        }
+    }
+
+    // `params` must have the same layout the effect was created with.
+    pub fn set_params(&self, rc: &mut RenderContext, params: &impl Pod) {
+        rc.queue.write_buffer(&self.uniform_buf, 0, bytemuck::bytes_of(params));
+    }
+}
@@ -0,0 +1,27 @@
+struct Params {
+    threshold: f32,
+    intensity: f32,
+    radius: f32,
+    _pad: f32,
+};
+@group(0) @binding(2) var<uniform> params: Params;
+
+fn bright(uv: vec2<f32>) -> vec3<f32> {
+    let c = textureSample(source_texture, source_sampler, uv).rgb;
+    return max(c - vec3<f32>(params.threshold), vec3<f32>(0.0));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source_texture, source_sampler, in.uv);
+    let texel = params.radius / vec2<f32>(textureDimensions(source_texture));
+    // single pass approximation: gaussian-ish ring of taps over the bright parts
+    var glow = bright(in.uv) * 0.2;
+    for (var i = 0; i < 8; i++) {
+        let angle = f32(i) * 0.785398;
+        let dir = vec2<f32>(cos(angle), sin(angle)) * texel;
+        glow += bright(in.uv + dir) * 0.06;
+        glow += bright(in.uv + dir * 2.0) * 0.04;
+    }
+    return vec4<f32>(color.rgb + glow * params.intensity, color.a);
+}
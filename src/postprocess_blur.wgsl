@@ -0,0 +1,22 @@
+struct Params {
+    direction: vec2<f32>,
+    radius: f32,
+    _pad: f32,
+};
+@group(0) @binding(2) var<uniform> params: Params;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = params.direction * params.radius / vec2<f32>(textureDimensions(source_texture));
+    // 9 tap separable gaussian, run once per direction
+    var color = textureSample(source_texture, source_sampler, in.uv) * 0.227027;
+    color += textureSample(source_texture, source_sampler, in.uv + texel * 1.0) * 0.194595;
+    color += textureSample(source_texture, source_sampler, in.uv - texel * 1.0) * 0.194595;
+    color += textureSample(source_texture, source_sampler, in.uv + texel * 2.0) * 0.121622;
+    color += textureSample(source_texture, source_sampler, in.uv - texel * 2.0) * 0.121622;
+    color += textureSample(source_texture, source_sampler, in.uv + texel * 3.0) * 0.054054;
+    color += textureSample(source_texture, source_sampler, in.uv - texel * 3.0) * 0.054054;
+    color += textureSample(source_texture, source_sampler, in.uv + texel * 4.0) * 0.016216;
+    color += textureSample(source_texture, source_sampler, in.uv - texel * 4.0) * 0.016216;
+    return color;
+}
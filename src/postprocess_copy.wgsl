@@ -0,0 +1,9 @@
+struct Params {
+    unused: vec4<f32>,
+};
+@group(0) @binding(2) var<uniform> params: Params;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(source_texture, source_sampler, in.uv);
+}
@@ -0,0 +1,20 @@
+struct Params {
+    scanline_intensity: f32,
+    scanline_count: f32,
+    curvature: f32,
+    _pad: f32,
+};
+@group(0) @binding(2) var<uniform> params: Params;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // barrel distortion around the center of the screen
+    let centered = in.uv * 2.0 - vec2<f32>(1.0);
+    let offset = centered.yx * centered.yx * params.curvature;
+    let warped = centered + centered * offset;
+    let uv = warped * 0.5 + vec2<f32>(0.5);
+    let color = textureSample(source_texture, source_sampler, uv);
+    let inside = all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0));
+    let scanline = 1.0 - params.scanline_intensity * (0.5 + 0.5 * sin(uv.y * params.scanline_count * 6.283185));
+    return select(vec4<f32>(0.0, 0.0, 0.0, 1.0), vec4<f32>(color.rgb * scanline, color.a), inside);
+}
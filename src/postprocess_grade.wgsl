@@ -0,0 +1,19 @@
+struct Params {
+    brightness: f32,
+    contrast: f32,
+    saturation: f32,
+    gamma: f32,
+    tint: vec4<f32>,
+};
+@group(0) @binding(2) var<uniform> params: Params;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source_texture, source_sampler, in.uv);
+    var rgb = color.rgb + vec3<f32>(params.brightness);
+    rgb = (rgb - vec3<f32>(0.5)) * params.contrast + vec3<f32>(0.5);
+    let luma = dot(rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+    rgb = mix(vec3<f32>(luma), rgb, params.saturation);
+    rgb = pow(max(rgb, vec3<f32>(0.0)), vec3<f32>(1.0 / params.gamma));
+    return vec4<f32>(rgb * params.tint.rgb, color.a);
+}
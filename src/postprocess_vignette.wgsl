@@ -0,0 +1,16 @@
+struct Params {
+    color: vec4<f32>,
+    intensity: f32,
+    radius: f32,
+    softness: f32,
+    _pad: f32,
+};
+@group(0) @binding(2) var<uniform> params: Params;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source_texture, source_sampler, in.uv);
+    let dist = distance(in.uv, vec2<f32>(0.5, 0.5));
+    let amount = smoothstep(params.radius, params.radius + params.softness, dist) * params.intensity;
+    return vec4<f32>(mix(color.rgb, params.color.rgb, amount), color.a);
+}
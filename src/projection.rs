@@ -0,0 +1,94 @@
+use winit::dpi::PhysicalSize;
+
+use crate::{
+    geom::Rect,
+    mat::{vec2, Mat4, Vec2},
+};
+
+// How a design resolution is shown in a window of a different size. Game
+// code works in design coordinates, y down from the top left like
+// Mat4::ortho, and they stay put when the window is resized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Policy {
+    // the design fills the window, squashed if the aspect ratios differ
+    Stretch,
+    // the whole design is shown as large as fits, with bars on two sides
+    Fit,
+    // the design covers the window, with two of its sides cut off
+    Fill,
+    // the design's width fills the window and more or less of its height
+    // is shown, centered
+    FixedWidth,
+    // like FixedWidth, sideways
+    FixedHeight,
+}
+
+// A projection and the viewport to use it with, in window pixels from the
+// top left. `visible` is the part of design space that ends up in the
+// viewport, which is larger than the design for FixedWidth and FixedHeight
+// in some windows.
+#[derive(Clone, Copy, Debug)]
+pub struct Projection {
+    pub matrix: Mat4,
+    pub viewport: Rect,
+    pub visible: Rect,
+}
+
+impl Projection {
+    pub fn new(policy: Policy, design: Vec2, window: PhysicalSize<u32>) -> Projection {
+        let window = vec2(window.width.max(1) as f32, window.height.max(1) as f32);
+        let design_rect = Rect::new(vec2(0.0, 0.0), design);
+        let whole_window = Rect::new(vec2(0.0, 0.0), window);
+        // the visible design size, centered on the design, when the whole
+        // window is used
+        let centered = |size: Vec2| Rect::from_center(design_rect.center(), size);
+        let (viewport, visible) = match policy {
+            Policy::Stretch => (whole_window, design_rect),
+            Policy::Fit => {
+                let scale = (window.x / design.x).min(window.y / design.y);
+                let size = design * scale;
+                // whole pixel offsets keep the edges sharp
+                (Rect::from_pos_size(((window - size) / 2.0).map(f32::floor), size), design_rect)
+            },
+            Policy::Fill => {
+                let scale = (window.x / design.x).max(window.y / design.y);
+                (whole_window, centered(window / scale))
+            },
+            Policy::FixedWidth => (whole_window, centered(vec2(design.x, window.y * design.x / window.x))),
+            Policy::FixedHeight => (whole_window, centered(vec2(window.x * design.y / window.y, design.y))),
+        };
+        Projection {
+            matrix: Mat4::ortho_bounds(visible.min.x, visible.max.x, visible.max.y, visible.min.y, 0.0, 1.0),
+            viewport,
+            visible,
+        }
+    }
+
+    // The whole window at one unit per logical pixel, like
+    // Mat4::ortho_logical, for UI that should keep its size on any screen
+    pub fn logical(window: PhysicalSize<u32>, scale_factor: f64) -> Projection {
+        let logical = window.to_logical::<f32>(scale_factor);
+        Projection {
+            matrix: Mat4::ortho_logical(window, scale_factor),
+            viewport: Rect::new(vec2(0.0, 0.0), vec2(window.width as f32, window.height as f32)),
+            visible: Rect::new(vec2(0.0, 0.0), vec2(logical.width, logical.height)),
+        }
+    }
+
+    pub fn set_viewport(&self, rpass: &mut wgpu::RenderPass) {
+        let Rect { min, max } = self.viewport;
+        rpass.set_viewport(min.x, min.y, max.x - min.x, max.y - min.y, 0.0, 1.0);
+    }
+
+    // A window position, like InputState::mouse_position, in design
+    // coordinates. None outside the viewport.
+    pub fn window_to_design(&self, position: Vec2) -> Option<Vec2> {
+        self.viewport.contains(position).then(|| {
+            self.visible.min + (position - self.viewport.min) / self.viewport.size() * self.visible.size()
+        })
+    }
+
+    pub fn design_to_window(&self, position: Vec2) -> Vec2 {
+        self.viewport.min + (position - self.visible.min) / self.visible.size() * self.viewport.size()
+    }
+}
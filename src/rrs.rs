@@ -1,7 +1,8 @@
 use strum::EnumDiscriminants;
 use std::collections::HashMap;
 use crate::win::RenderContext;
-use crate::mat::Mat4;
+use crate::geom::Rect;
+use crate::mat::{vec2, vec4, Mat4, Vec2};
 
 #[derive(Debug, EnumDiscriminants)]
 #[strum_discriminants(derive(Hash))]
@@ -9,10 +10,23 @@ pub enum Entry {
     Simple,
     Square(crate::square::RenderParams),
     Textured(crate::textured::RenderParams),
+    Text(crate::text::RenderParams),
 }
 
 pub struct Settings {
     pub projection: Mat4,
+    // skip entries whose bounds are outside the projection's visible area
+    pub cull: bool,
+    // the size in pixels of the target being drawn to, when sprites should
+    // be moved onto whole pixels of it
+    pub snap: Option<winit::dpi::PhysicalSize<u32>>,
+}
+
+// Counts for one or more calls to RenderRecordSystem::render
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FrameStats {
+    pub drawn: usize,
+    pub culled: usize,
 }
 
 #[derive(Debug)]
@@ -24,10 +38,12 @@ pub enum Update<'a> {
 #[derive(Debug)]
 pub enum UpdateArgs<'a> {
     Textured(crate::textured::UpdateArgs<'a>),
+    Text(crate::text::UpdateArgs<'a>),
 }
 #[derive(Debug)]
 pub enum UpdateReturn {
     Textured(crate::textured::UpdateReturn),
+    Text(crate::text::UpdateReturn),
 }
 
 pub trait RenderConstruct {
@@ -56,6 +72,53 @@ pub struct Record {
     pub entries: Vec<Entry>,
 }
 
+impl Entry {
+    // World space bounds, or None for entries that are always drawn
+    pub fn bounds(&self) -> Option<Rect> {
+        match self {
+            Entry::Square(params) => Some(params.bounds()),
+            Entry::Textured(params) => Some(params.bounds()),
+            Entry::Simple | Entry::Text(_) => None,
+        }
+    }
+
+    pub fn is_visible(&self, area: &Rect) -> bool {
+        self.bounds().is_none_or(|bounds| bounds.overlaps(area))
+    }
+}
+
+impl Settings {
+    // The world space rect that ends up on screen, None when the projection
+    // can't be inverted
+    pub fn visible_area(&self) -> Option<Rect> {
+        let clip = Rect::new(Vec2::splat(-1.0), Vec2::splat(1.0));
+        Some(clip.transform(&self.projection.inverse()?))
+    }
+
+    // Moves a unit square matrix like Mat4::box2d so its corners land on
+    // whole pixels of the target, when `snap` is set. Sizes aren't changed,
+    // so a sprite also needs a whole number of pixels to be exact.
+    pub fn snap_matrix(&self, matrix: &Mat4) -> Mat4 {
+        let Some(size) = self.snap else { return *matrix };
+        let (width, height) = (size.width as f32, size.height as f32);
+        // clip space to pixels, y down
+        let to_pixels = Mat4::translate2d(vec2(width / 2.0, height / 2.0))
+            * Mat4::scale2d(vec2(width / 2.0, -height / 2.0))
+            * self.projection;
+        let Some(from_pixels) = to_pixels.inverse() else { return *matrix };
+        let corner = (to_pixels * *matrix) % vec4(-0.5, -0.5, 0.0, 1.0);
+        let offset = from_pixels % vec4(corner.x.round() - corner.x, corner.y.round() - corner.y, 0.0, 0.0);
+        Mat4::translate2d(vec2(offset.x, offset.y)) * *matrix
+    }
+}
+
+impl std::ops::AddAssign for FrameStats {
+    fn add_assign(&mut self, other: FrameStats) {
+        self.drawn += other.drawn;
+        self.culled += other.culled;
+    }
+}
+
 impl Record {
     pub fn new() -> Self {
         Self {
@@ -84,11 +147,19 @@ impl RenderRecordSystem {
             .map(|index| &mut self.renderers[*index])
             .map(|renderer| renderer.load(rc, update));
     }
-    pub fn render(&mut self, rc: &mut RenderContext, rpass: &mut wgpu::RenderPass<'_>, rr: &Record, settings: &Settings) {
+    pub fn render(&mut self, rc: &mut RenderContext, rpass: &mut wgpu::RenderPass<'_>, rr: &Record, settings: &Settings) -> FrameStats {
+        let mut stats = FrameStats::default();
+        let area = settings.visible_area().filter(|_| settings.cull);
         for renderer in &mut self.renderers {
             renderer.pre_render(rc, rr, settings);
         }
         for entry in &rr.entries {
+            // culled entries never reach their renderer, so nothing is uploaded for them
+            if area.is_some_and(|area| !entry.is_visible(&area)) {
+                stats.culled += 1;
+                continue;
+            }
+            stats.drawn += 1;
             self.renderer_mapping.get_mut(&entry.into())
                 .map(|index| &mut self.renderers[*index])
                 .map(|renderer| renderer.render(rc, rpass, entry, settings));
@@ -96,5 +167,6 @@ impl RenderRecordSystem {
         for renderer in &mut self.renderers {
             renderer.post_render(rc, rr, settings);
         }
+        stats
     }
 }
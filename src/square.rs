@@ -1,5 +1,6 @@
 use crate::{rrs::{self, Entry, EntryDiscriminants, Record, RenderConstruct, Settings, Update}, win::RenderContext};
-use crate::mat::Mat4;
+use crate::geom::{Obb, Rect};
+use crate::mat::{Mat4, Vec4};
 use wgpu::util::DeviceExt;
 use std::{borrow::Cow, num::NonZero, ops::Range, str};
 
@@ -18,11 +19,21 @@ pub struct Renderer {
 pub struct RenderParams {
     pub matrix: Mat4,
     pub range: Range<u32>,
+    pub color: Vec4,
 }
 
-#[repr(C, packed)]
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 struct InstanceBuffer {
     matrix: Mat4,
+    color: Vec4,
+}
+
+impl RenderParams {
+    // the shader's unit square, in world space
+    pub fn bounds(&self) -> Rect {
+        Obb::from_matrix(&self.matrix).bounds()
+    }
 }
 
 impl InstanceBuffer {
@@ -34,6 +45,7 @@ impl InstanceBuffer {
             1 => Float32x4,
             2 => Float32x4,
             3 => Float32x4,
+            4 => Float32x4,
         ],
     };
 }
@@ -95,12 +107,19 @@ impl Construct {
             },
             primitive: Default::default(),
             depth_stencil: None,
-            multisample: Default::default(),
+            multisample: wgpu::MultisampleState {
+                count: rc.sample_count,
+                ..Default::default()
+            },
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
                 entry_point: Some("fs_main"),
                 compilation_options: Default::default(),
-                targets: &[Some(rc.surface_format.into())],
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: rc.surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
             }),
             multiview: None,
             cache: None,
@@ -153,10 +172,11 @@ impl rrs::Renderer for Renderer {
         self.current_buf = 0;
     }
 
-    fn render(&mut self, rc: &mut RenderContext, rpass: &mut wgpu::RenderPass, entry: &Entry, _: &Settings) {
+    fn render(&mut self, rc: &mut RenderContext, rpass: &mut wgpu::RenderPass, entry: &Entry, settings: &Settings) {
         let RenderParams {
             matrix,
             range,
+            color,
         } = match entry {
             Entry::Square(p) => p,
             _ => panic!("Failed to call correct renderer!"),
@@ -165,7 +185,8 @@ impl rrs::Renderer for Renderer {
         let buf_index = self.current_buf;
         self.current_buf += 1;
         let offset: u64 = (size_of::<InstanceBuffer>() * (buf_index as usize)).try_into().unwrap();
-        rc.queue.write_buffer(&self.instance_buf, offset, matrix.as_ref());
+        let instance = InstanceBuffer { matrix: settings.snap_matrix(matrix), color: *color };
+        rc.queue.write_buffer(&self.instance_buf, offset, bytemuck::bytes_of(&instance));
 
         rc.queue.write_buffer(&self.uniform_buf, 0, Mat4::identity().as_ref());
         rpass.set_pipeline(&self.pipeline);
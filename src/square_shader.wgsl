@@ -16,19 +16,28 @@ struct InstanceBuffer {
     @location(1) model_matrix_1: vec4<f32>,
     @location(2) model_matrix_2: vec4<f32>,
     @location(3) model_matrix_3: vec4<f32>, 
+    @location(4) color: vec4<f32>,
 }
 
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
 @vertex
 fn vs_main(@builtin(vertex_index) in_vertex_index: u32,
-            buffer: InstanceBuffer) -> @builtin(position) vec4<f32> {
+            buffer: InstanceBuffer) -> VertexOutput {
     let mat = mat4x4<f32>(buffer.model_matrix_0, buffer.model_matrix_1, buffer.model_matrix_2, buffer.model_matrix_3);
     let x = f32(vertices[in_vertex_index * 2]) - 0.5;
     let y = f32(vertices[in_vertex_index * 2 + 1]) - 0.5;
-    return projection * mat * vec4<f32>(x, y, 0.0, 1.0);
+    var out: VertexOutput;
+    out.clip_position = projection * mat * vec4<f32>(x, y, 0.0, 1.0);
+    out.color = buffer.color;
+    return out;
 }
 
 @fragment
-fn fs_main() -> @location(0) vec4<f32> {
-    return vec4<f32>(1.0, 1.0, 1.0, 1.0);
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
 }
 
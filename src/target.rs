@@ -0,0 +1,106 @@
+use crate::{
+    rrs::{Record, RenderRecordSystem, Settings},
+    texture::TextureInfo,
+    win::{multisampled_texture, RenderContext},
+};
+
+// An offscreen color texture that records can be rendered into. The resulting
+// `info` can be registered with `textured::Construct::init_texture` and drawn
+// like any other texture.
+pub struct RenderTarget {
+    pub info: TextureInfo,
+    pub size: winit::dpi::PhysicalSize<u32>,
+    // rendered into and resolved to `info` when MSAA is enabled
+    pub msaa_view: Option<wgpu::TextureView>,
+}
+
+// Color attachment for `view`, going through `msaa` and resolving into `view`
+// when the render context uses multisampling.
+pub fn color_attachment<'a>(
+    view: &'a wgpu::TextureView,
+    msaa: Option<&'a wgpu::TextureView>,
+    load: wgpu::LoadOp<wgpu::Color>,
+) -> wgpu::RenderPassColorAttachment<'a> {
+    wgpu::RenderPassColorAttachment {
+        view: msaa.unwrap_or(view),
+        resolve_target: msaa.map(|_| view),
+        ops: wgpu::Operations {
+            load,
+            store: wgpu::StoreOp::Store,
+        },
+    }
+}
+
+impl RenderTarget {
+    pub fn init(rc: &mut RenderContext, size: winit::dpi::PhysicalSize<u32>) -> RenderTarget {
+        let texture = rc.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Render target"),
+            size: wgpu::Extent3d {
+                width: size.width.max(1),
+                height: size.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // renderer pipelines are all built against the surface format
+            format: rc.surface_format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&Default::default());
+        let sampler = rc.device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let msaa_view = (rc.sample_count > 1).then(|| {
+            multisampled_texture(rc, size.width.max(1), size.height.max(1))
+                .create_view(&Default::default())
+        });
+        RenderTarget {
+            info: TextureInfo { texture, view, sampler },
+            size,
+            msaa_view,
+        }
+    }
+
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.info.view
+    }
+
+    pub fn attachment(&self, load: wgpu::LoadOp<wgpu::Color>) -> wgpu::RenderPassColorAttachment<'_> {
+        color_attachment(&self.info.view, self.msaa_view.as_ref(), load)
+    }
+
+    // Renders the record into this target and submits immediately. Renderers
+    // stage their instance data with queue.write_buffer, so every record has to
+    // be submitted before the next one is recorded.
+    pub fn render(
+        &self,
+        rc: &mut RenderContext,
+        rrs: &mut RenderRecordSystem,
+        record: &Record,
+        settings: &Settings,
+        clear: wgpu::Color,
+    ) {
+        let mut encoder = rc.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Render Target Encoder"),
+        });
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Target Pass"),
+                color_attachments: &[Some(self.attachment(wgpu::LoadOp::Clear(clear)))],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            rrs.render(rc, &mut rpass, record, settings);
+        }
+        rc.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
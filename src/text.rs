@@ -0,0 +1,977 @@
+use crate::{
+    mat::{vec2, vec4, Mat4, Vec2, Vec4},
+    rrs::{self, Entry, EntryDiscriminants, Record, RenderConstruct, RenderRecordSystem, Settings, Update},
+    util::{indirect_handles::{Handle, HandleTracker, WeakHandle}, WordSpaceIterable, WordSpaceIterator},
+    win::RenderContext,
+};
+use std::{borrow::Cow, collections::{HashMap, HashSet}, num::NonZero, str};
+use wgpu::util::DeviceExt;
+
+use self::{cache::GlyphCache, packing::{GlyphInfo, GlyphPacking}};
+
+pub mod cache;
+pub mod collection;
+pub mod layout;
+pub mod packing;
+pub mod sdf;
+pub mod shaping;
+
+pub fn default_characters() -> Vec<char> {
+    let mut chars = vec![0];
+    chars.append(&mut (32..127).collect::<Vec<u32>>());
+    chars.iter().map(|i| char::from_u32(*i).unwrap()).collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct GlyphMetrics {
+    pub glyph_pos: Vec2,
+    pub glyph_size: Vec2,
+    pub advance: f32,
+    pub lsb: f32, // left side bearing
+    pub tsb: f32, // top side bearing
+}
+
+struct GlyphBitmap {
+    width: usize,
+    height: usize,
+    buffer: Vec<u8>,
+    metrics: fontdue::Metrics,
+    char_code: char,
+}
+
+// A rasterized font atlas, one per font size, or one distance field atlas
+// for every size
+pub struct FontInfo {
+    pub image_buffer: Vec<u8>,
+    pub image_size: [u32; 2],
+    pub char_data: HashMap<char, GlyphMetrics>,
+    pub font_size: f32,
+    pub not_found_char: Option<char>,
+    pub height: f32,
+    pub ascent: f32,
+    pub kerning: HashMap<(char, char), f32>,
+    // spread of the distance field in atlas pixels, None for coverage bitmaps
+    pub distance_field: Option<f32>,
+    pub name: String,
+    pub ignore_chars: HashSet<char>,
+}
+
+// FontInfo without the image, for laying out text on the CPU
+#[derive(Clone, Debug)]
+pub struct FontMetricsInfo {
+    pub char_data: HashMap<char, GlyphMetrics>,
+    pub font_size: f32,
+    pub height: f32,
+    pub ascent: f32,
+    pub kerning: HashMap<(char, char), f32>,
+    pub not_found_char: Option<char>,
+    pub ignore_chars: HashSet<char>,
+}
+
+impl std::fmt::Debug for FontInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FontInfo")
+            .field("name", &self.name)
+            .field("image_size", &self.image_size)
+            .finish_non_exhaustive()
+    }
+}
+
+pub fn make_font_infos<'a, T>(bytes: &[u8], font_sizes: &[f32], char_codes: T, not_found_char: Option<&'a char>, name: String)
+        -> Result<Vec<FontInfo>, String>
+        where T: Iterator<Item = &'a char> {
+    let char_codes = collect_chars(char_codes, not_found_char);
+    let font = load_font(bytes, font_sizes.iter().copied().reduce(f32::max).ok_or("Received no font sizes")?)?;
+    font_sizes.iter().map(|font_size| {
+        let glyphs: Vec<GlyphBitmap> = char_codes.iter().map(|char_code| {
+            let (metrics, bitmap) = font.rasterize(*char_code, *font_size);
+            GlyphBitmap {
+                width: metrics.width,
+                height: metrics.height,
+                buffer: bitmap,
+                metrics,
+                char_code: *char_code,
+            }
+        }).collect();
+        build_font_info(&font, &glyphs, &char_codes, *font_size, not_found_char, None, &name)
+    }).collect()
+}
+
+// Makes a single atlas of signed distance fields that stays sharp when drawn
+// at any scale. `spread` is how many pixels of distance are stored around
+// each glyph, which bounds how wide outlines and shadow softness can get.
+pub fn make_sdf_font_info<'a, T>(bytes: &[u8], font_size: f32, char_codes: T, not_found_char: Option<&'a char>, spread: u32, name: String)
+        -> Result<FontInfo, String>
+        where T: Iterator<Item = &'a char> {
+    let char_codes = collect_chars(char_codes, not_found_char);
+    let font = load_font(bytes, font_size)?;
+    let padding = spread as usize;
+    let glyphs: Vec<GlyphBitmap> = char_codes.iter().map(|char_code| {
+        let (metrics, bitmap) = font.rasterize(*char_code, font_size);
+        let (width, height, buffer) = if metrics.width == 0 || metrics.height == 0 {
+            (0, 0, vec![])
+        } else {
+            (metrics.width + padding * 2, metrics.height + padding * 2,
+                sdf::distance_field(&bitmap, metrics.width, metrics.height, padding))
+        };
+        GlyphBitmap {
+            width,
+            height,
+            buffer,
+            metrics,
+            char_code: *char_code,
+        }
+    }).collect();
+    build_font_info(&font, &glyphs, &char_codes, font_size, not_found_char, Some(spread as f32), &name)
+}
+
+// allow multiple iterations of char_codes
+fn collect_chars<'a>(char_codes: impl Iterator<Item = &'a char>, not_found_char: Option<&'a char>) -> Vec<char> {
+    let mut char_codes: Vec<char> = char_codes.chain(not_found_char).copied().collect();
+    char_codes.sort();
+    char_codes.dedup();
+    char_codes
+}
+
+fn load_font(bytes: &[u8], scale: f32) -> Result<fontdue::Font, String> {
+    let font_settings = fontdue::FontSettings {
+        collection_index: 0,
+        scale,
+        load_substitutions: true,
+    };
+    Ok(fontdue::Font::from_bytes(bytes, font_settings)?)
+}
+
+// Packs the glyph bitmaps into an atlas. Bitmaps may be bigger than the glyph
+// they hold, in which case the glyph is centered in them.
+fn build_font_info(
+    font: &fontdue::Font,
+    glyphs: &[GlyphBitmap],
+    char_codes: &[char],
+    font_size: f32,
+    not_found_char: Option<&char>,
+    distance_field: Option<f32>,
+    name: &str,
+) -> Result<FontInfo, String> {
+    // pad each glyph by 1 px on the bottom and right edges, so every box
+    // ends up with an exactly 1px boundary
+    let padded: Vec<GlyphInfo<char>> = glyphs.iter().map(|glyph| {
+        GlyphInfo {
+            id: glyph.char_code,
+            width: glyph.width as u32 + 1,
+            height: glyph.height as u32 + 1
+        }
+    }).collect();
+
+    let packing = packing::do_font_packing(&padded)
+        .ok_or_else(|| format!("Error loading font {} size {}: could not pack", name, font_size))?;
+    let image_buffer = apply_packing(glyphs, &packing);
+    let (height, ascent) = font.horizontal_line_metrics(font_size)
+        .map_or((font_size, font_size), |metrics| (metrics.new_line_size, metrics.ascent));
+    // the pair table grows quadratically, so only ASCII pairs are kerned
+    let ascii: Vec<char> = char_codes.iter().copied().filter(char::is_ascii).collect();
+    let kerning = ascii.iter()
+        .flat_map(|left| ascii.iter().map(move |right| (*left, *right)))
+        .filter_map(|(left, right)| {
+            let kern = font.horizontal_kern(left, right, font_size)?;
+            (kern != 0.0).then_some(((left, right), kern))
+        })
+        .collect();
+
+    Ok(FontInfo {
+        image_buffer,
+        image_size: [packing.width(), packing.height()],
+        char_data: glyphs.iter().map(|glyph| (
+            glyph.char_code,
+            GlyphMetrics {
+                glyph_pos: {
+                    let [x, y] = packing.get_glyph_pos(glyph.char_code).unwrap();
+                    let margin_x = glyph.width.saturating_sub(glyph.metrics.width) / 2;
+                    let margin_y = glyph.height.saturating_sub(glyph.metrics.height) / 2;
+                    vec2((x as usize + margin_x) as f32, (y as usize + margin_y) as f32)
+                },
+                glyph_size: vec2(glyph.metrics.width as f32, glyph.metrics.height as f32),
+                advance: glyph.metrics.advance_width,
+                // the bitmap is placed at whole pixel offsets, not the outline bounds
+                lsb: glyph.metrics.xmin as f32,
+                tsb: (glyph.metrics.ymin + glyph.metrics.height as i32) as f32,
+            }
+        )).collect(),
+        font_size,
+        not_found_char: not_found_char.copied(),
+        height,
+        ascent,
+        kerning,
+        distance_field,
+        name: format!("{}-{}", name, font_size),
+        ignore_chars: ['\r'].into(),
+    })
+}
+
+// applies packing by copying glyphs to positions specified by the packing into a new vector
+fn apply_packing(glyphs: &[GlyphBitmap], packing: &GlyphPacking<char>) -> Vec<u8> {
+    let width = packing.width() as usize;
+    let height = packing.height() as usize;
+    let mut image = vec![0; width * height];
+    for glyph in glyphs {
+        let [x0, y0] = packing.get_glyph_pos(glyph.char_code).unwrap();
+        let (x0, y0) = (x0 as usize, y0 as usize);
+        for y in 0..glyph.height {
+            let row = &glyph.buffer[y * glyph.width..(y + 1) * glyph.width];
+            image[(y0 + y) * width + x0..(y0 + y) * width + x0 + glyph.width].copy_from_slice(row);
+        }
+    }
+    image
+}
+
+pub trait BaseFontInfoContainer {
+    fn line_height(&self) -> f32;
+    // distance from the top of a line to its baseline
+    fn ascent(&self) -> f32;
+    fn font_size(&self) -> f32;
+    // extra advance between two characters, usually negative
+    fn kerning(&self, left: char, right: char) -> f32;
+    fn get_metrics<'a>(&'a self, c: &char) -> Option<&'a GlyphMetrics>;
+    // metrics by glyph index, for shaped text. Fonts keyed by char don't
+    // have them.
+    fn get_glyph_metrics(&self, _glyph: u16) -> Option<&GlyphMetrics> {
+        None
+    }
+    fn get_metrics_info(&self) -> FontMetricsInfo;
+    fn is_char_valid(&self, c: &char) -> bool;
+}
+
+impl BaseFontInfoContainer for FontInfo {
+    fn line_height(&self) -> f32 {
+        self.height
+    }
+
+    fn ascent(&self) -> f32 {
+        self.ascent
+    }
+
+    fn font_size(&self) -> f32 {
+        self.font_size
+    }
+
+    fn kerning(&self, left: char, right: char) -> f32 {
+        self.kerning.get(&(left, right)).copied().unwrap_or(0.0)
+    }
+
+    fn get_metrics<'a>(&'a self, c: &char) -> Option<&'a GlyphMetrics> {
+        if self.ignore_chars.contains(c) {
+            return None
+        }
+        self.char_data.get(c)
+            .or_else(|| self.not_found_char
+                .and_then(|c| self.char_data.get(&c)))
+    }
+
+    fn get_metrics_info(&self) -> FontMetricsInfo {
+        FontMetricsInfo {
+            char_data: self.char_data.clone(),
+            font_size: self.font_size,
+            height: self.height,
+            ascent: self.ascent,
+            kerning: self.kerning.clone(),
+            not_found_char: self.not_found_char,
+            ignore_chars: self.ignore_chars.clone(),
+        }
+    }
+
+    fn is_char_valid(&self, c: &char) -> bool {
+        if self.ignore_chars.contains(c) {
+            return false
+        }
+        self.char_data.contains_key(c)
+    }
+}
+
+impl BaseFontInfoContainer for FontMetricsInfo {
+    fn line_height(&self) -> f32 {
+        self.height
+    }
+
+    fn ascent(&self) -> f32 {
+        self.ascent
+    }
+
+    fn font_size(&self) -> f32 {
+        self.font_size
+    }
+
+    fn kerning(&self, left: char, right: char) -> f32 {
+        self.kerning.get(&(left, right)).copied().unwrap_or(0.0)
+    }
+
+    fn get_metrics<'a>(&'a self, c: &char) -> Option<&'a GlyphMetrics> {
+        if self.ignore_chars.contains(c) {
+            return None
+        }
+        self.char_data.get(c)
+            .or_else(|| self.not_found_char
+                .and_then(|c| self.char_data.get(&c)))
+    }
+
+    fn get_metrics_info(&self) -> FontMetricsInfo {
+        self.clone()
+    }
+
+    fn is_char_valid(&self, c: &char) -> bool {
+        if self.ignore_chars.contains(c) {
+            return false
+        }
+        self.char_data.contains_key(c)
+    }
+}
+
+pub trait FontInfoContainer<T: BaseFontInfoContainer> {
+    fn text_width(&self, text: &str) -> f32;
+    // splits lines (word wrap) using maximum line length, new line characters, and white space
+    fn split_lines<'a>(&'a self, text: &'a str, max_length: Option<f32>) -> FontLineIterator<'a, T>;
+}
+
+pub struct FontLineIterator<'a, T: BaseFontInfoContainer> {
+    info: &'a T,
+    max_length: f32,
+    words: WordSpaceIterator<'a>,
+    current_word: Option<String>,
+}
+
+impl<T: BaseFontInfoContainer> Iterator for FontLineIterator<'_, T> {
+    type Item = String;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut cur_line = String::new();
+        let mut cur_line_adv = 0.0;
+
+        let mut start = self.current_word.take();
+        let mut empty = true;
+        while let Some(word) = start.take().or_else(|| self.words.next()) {
+            empty = false;
+            let mut word_adv = 0.0;
+            let mut word_building = String::new();
+            let mut word_building_len = 0;
+            for c in word.chars() {
+                if c == '\n' {
+                    // the rest of the newline run starts the following lines
+                    let rest = &word[c.len_utf8()..];
+                    self.current_word = (!rest.is_empty()).then(|| rest.to_string());
+                    return Some(cur_line + &word_building)
+                }
+                let Some(metrics) = self.info.get_metrics(&c) else { continue };
+                match c {
+                    ' ' | '\t' => {
+                        let next_length = cur_line_adv + word_adv + metrics.advance;
+                        if next_length > self.max_length {
+                            return Some(cur_line)
+                        } else {
+                            word_adv += metrics.advance;
+                            word_building.push(c);
+                            word_building_len += c.len_utf8();
+                        }
+                    },
+                    _ => {
+                        let next_length = cur_line_adv + word_adv + metrics.lsb + metrics.glyph_size.x;
+                        if next_length > self.max_length {
+                            if cur_line.is_empty() {
+                                // only one big word that needs to be cut down the middle
+                                if word_building.is_empty() {
+                                    // this character is just really big, needs its own line
+                                    // split off the rest of the word into current_word
+                                    let rest = &word[c.len_utf8()..];
+                                    self.current_word = (!rest.is_empty()).then(|| rest.to_string());
+                                    return Some(c.to_string());
+                                } else {
+                                    // we have to split at the end of word_building
+                                    self.current_word = Some(word[word_building_len..].to_string());
+                                    return Some(word_building)
+                                }
+                            } else {
+                                // we can wrap to the next line with the current word
+                                self.current_word = Some(word);
+                                return Some(cur_line)
+                            }
+                        } else {
+                            // we can add the letter to the line we're building
+                            word_adv += metrics.advance;
+                            word_building.push(c);
+                            word_building_len += c.len_utf8();
+                        }
+                    },
+                }
+            }
+            cur_line_adv += word_adv;
+            cur_line += &word_building;
+        }
+
+        // we reached the end of the word iterator
+        // if we never iterated we return None
+        // else we return whatever is in the current line
+        if empty {
+            None
+        } else {
+            Some(cur_line)
+        }
+    }
+}
+
+impl<T> FontInfoContainer<T> for T where T: BaseFontInfoContainer {
+    fn text_width(&self, text: &str) -> f32 {
+        struct W {cur_adv: f32, longest: f32}
+        text.chars().fold(W {cur_adv: 0.0, longest: 0.0}, |sum: W, c| match (c, self.get_metrics(&c)) {
+            ('\n', _) => W {cur_adv: 0.0, longest: sum.longest}, // new line
+            (_, None) => sum, // ignore non-characters
+            (_, Some(metrics)) => W {
+                cur_adv: sum.cur_adv + metrics.advance,
+                // true size of line is the last character's advance plus
+                // current character's lsb + width
+                longest: f32::max(sum.cur_adv + metrics.lsb + metrics.glyph_size.x, sum.longest)
+            },
+        }).longest
+    }
+
+    fn split_lines<'a>(&'a self, text: &'a str, max_length: Option<f32>) -> FontLineIterator<'a, T> {
+        FontLineIterator {
+            words: text.chars().words_spaces(),
+            current_word: None,
+            info: self,
+            max_length: max_length.unwrap_or(f32::MAX),
+        }
+    }
+}
+
+pub struct Construct(Option<Renderer>);
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Font;
+
+pub struct Renderer {
+    pipeline: wgpu::RenderPipeline,
+    sdf_pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buf: wgpu::Buffer,
+    instance_buf: wgpu::Buffer,
+    instance_buf_count: usize,
+    current_buf: u32,
+    // instances of each text entry, laid out in pre_render and drawn in order
+    prepared: Vec<Vec<(usize, InstanceBuffer)>>,
+    current_entry: usize,
+    // counts pre_render calls, so glyph caches know what's drawn this frame
+    frame: u64,
+    fonts: HandleTracker<Font, FontData>,
+}
+
+// `position` is the left end of the first line's baseline. `scale` is
+// relative to the size the font was rasterized at, which only looks right
+// for distance field fonts.
+#[derive(Debug)]
+pub struct RenderParams {
+    pub font: WeakHandle<Font>,
+    pub text: String,
+    pub position: Vec2,
+    pub color: Vec4,
+    pub scale: f32,
+    pub effects: Effects,
+}
+
+// Widths and offsets are in screen pixels. Effects are only drawn for
+// distance field fonts, and can't reach further than the font's spread.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Effects {
+    pub outline: Option<Outline>,
+    pub shadow: Option<Shadow>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Outline {
+    pub width: f32,
+    pub color: Vec4,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Shadow {
+    pub offset: Vec2,
+    pub softness: f32,
+    pub color: Vec4,
+}
+
+#[derive(Debug)]
+pub enum UpdateArgs<'a> {
+    Atlas(&'a FontInfo, wgpu::FilterMode),
+    Cache(Box<GlyphCache>, wgpu::FilterMode),
+}
+#[derive(Debug)]
+pub struct UpdateReturn(Handle<Font>);
+
+struct FontData {
+    glyphs: Glyphs,
+    pages: Vec<AtlasPage>,
+    sampler: wgpu::Sampler,
+    image_size: Vec2,
+    distance_field: Option<f32>,
+}
+
+enum Glyphs {
+    Atlas(FontMetricsInfo),
+    Cache(Box<GlyphCache>),
+}
+
+struct AtlasPage {
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct InstanceBuffer {
+    matrix: Mat4,
+    texture_pos: Vec2,
+    texture_scale: Vec2,
+    color: Vec4,
+    // only read by the distance field shader
+    outline_color: Vec4,
+    // outline width and softness in atlas pixels, then the spread
+    effect: Vec4,
+}
+
+impl InstanceBuffer {
+    pub const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: size_of::<Self>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &wgpu::vertex_attr_array![
+            0 => Float32x4,
+            1 => Float32x4,
+            2 => Float32x4,
+            3 => Float32x4,
+            4 => Float32x2,
+            5 => Float32x2,
+            6 => Float32x4,
+            7 => Float32x4,
+            8 => Float32x4,
+        ],
+    };
+}
+
+impl Construct {
+    pub fn init(rc: &mut RenderContext) -> Construct {
+        let bind_group_layout = rc.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("font_texture_bind_group_layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: Some(NonZero::new(std::mem::size_of::<Mat4>() as u64).unwrap()),
+                },
+                count: None,
+            }, wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }, wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            }],
+        });
+        let uniform_buf = rc.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: Mat4::identity().as_ref(),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+        });
+        let instance_buf = rc.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: &[],
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::VERTEX,
+        });
+
+        let pipeline_layout = rc.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let create_pipeline = |label: &str, source: &[u8]| {
+            let shader = rc.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(label),
+                source: wgpu::ShaderSource::Wgsl(Cow::from(str::from_utf8(source).unwrap())),
+            });
+            rc.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    compilation_options: Default::default(),
+                    buffers: &[InstanceBuffer::LAYOUT],
+                },
+                primitive: Default::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: rc.sample_count,
+                    ..Default::default()
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some("fs_main"),
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: rc.surface_format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+                cache: None,
+            })
+        };
+        let pipeline = create_pipeline("font_shader", include_bytes!(env!("FONT_SHADER")));
+        let sdf_pipeline = create_pipeline("font_sdf_shader", include_bytes!(env!("FONT_SDF_SHADER")));
+        Construct(Some(Renderer {
+            pipeline,
+            sdf_pipeline,
+            bind_group_layout,
+            uniform_buf,
+            instance_buf,
+            instance_buf_count: 0,
+            current_buf: 0,
+            prepared: vec![],
+            current_entry: 0,
+            frame: 0,
+            fonts: Default::default(),
+        }))
+    }
+
+    // Uploads the font's atlas. Nearest filtering keeps small bitmap text
+    // crisp as long as it's drawn at its rasterized size, distance field
+    // fonts need linear filtering.
+    pub fn init_font(
+        &mut self,
+        rc: &mut RenderContext,
+        rrs: &mut RenderRecordSystem,
+        font_info: &FontInfo,
+        filter: wgpu::FilterMode,
+    ) -> Handle<Font> {
+        Self::update(rc, rrs, UpdateArgs::Atlas(font_info, filter))
+    }
+
+    // Hands the cache to the renderer, which fills in its pages as text is
+    // drawn with it
+    pub fn init_cached_font(
+        &mut self,
+        rc: &mut RenderContext,
+        rrs: &mut RenderRecordSystem,
+        cache: GlyphCache,
+        filter: wgpu::FilterMode,
+    ) -> Handle<Font> {
+        Self::update(rc, rrs, UpdateArgs::Cache(Box::new(cache), filter))
+    }
+
+    fn update(rc: &mut RenderContext, rrs: &mut RenderRecordSystem, args: UpdateArgs) -> Handle<Font> {
+        let args = Update::Args(rrs::UpdateArgs::Text(args));
+        match rrs.update(rc, &EntryDiscriminants::Text, args).expect("Font update returned None") {
+            Update::Return(rrs::UpdateReturn::Text(update_return)) => update_return.0,
+            other_update => panic!("Invalid update returned for text renderer: {:?}", other_update),
+        }
+    }
+}
+
+impl RenderConstruct for Construct {
+    type Renderer = Renderer;
+    type DrawParam = RenderParams;
+
+    fn init_renderer(&mut self) -> Renderer {
+        self.0.take().expect("Cannot instantiate multiple renderers for a construct")
+    }
+
+    fn draw(&mut self, _rc: &mut RenderContext, record: &mut Record, data: RenderParams) {
+        record.entries.push(Entry::Text(data));
+    }
+}
+
+impl Renderer {
+    // Returns each instance with the atlas page it samples, sorted by page.
+    // Cached fonts use the lines prepare_cache shaped, the glyphs of atlas
+    // fonts are looked up by char.
+    fn layout(font: &FontData, params: &RenderParams, lines: &[Vec<shaping::ShapedRun>]) -> Vec<(usize, InstanceBuffer)> {
+        let span = layout::Span { text: &params.text, font: 0, color: params.color };
+        match &font.glyphs {
+            Glyphs::Atlas(metrics) => {
+                let text_layout = layout::layout(std::slice::from_ref(metrics), &[span], &Default::default());
+                Self::instances(&text_layout, metrics.ascent(), |_| 0, font, params)
+            },
+            Glyphs::Cache(cache) => {
+                let fonts = std::slice::from_ref(cache.as_ref());
+                let text_layout = layout::layout_shaped(fonts, lines, &Default::default());
+                let page = |glyph: &layout::PositionedGlyph| glyph.glyph.and_then(|glyph| cache.glyph_page(glyph)).unwrap_or(0);
+                Self::instances(&text_layout, cache.ascent(), page, font, params)
+            },
+        }
+    }
+
+    fn instances(
+        text_layout: &layout::Layout,
+        ascent: f32,
+        page: impl Fn(&layout::PositionedGlyph) -> usize,
+        font: &FontData,
+        params: &RenderParams,
+    ) -> Vec<(usize, InstanceBuffer)> {
+        // the layout starts at the top of the first line
+        let origin = params.position - vec2(0.0, ascent * params.scale);
+        let Some(spread) = font.distance_field else {
+            let mut instances: Vec<_> = text_layout.glyphs.iter()
+                .map(|glyph| {
+                    // snap to whole pixels so the atlas is sampled texel for texel
+                    let pos = (origin + glyph.position * params.scale).map(f32::round);
+                    (page(glyph), font.instance(pos, glyph.size * params.scale, glyph.atlas_pos, glyph.size, glyph.color))
+                })
+                .collect();
+            instances.sort_by_key(|(page, _)| *page);
+            return instances;
+        };
+
+        // quads cover the whole distance field so effects aren't cut off
+        let glyph_instance = |glyph: &layout::PositionedGlyph, offset: Vec2, color: Vec4| {
+            let padding = Vec2::splat(spread);
+            let pos = origin + offset + (glyph.position - padding) * params.scale;
+            let size = glyph.size + padding * 2.0;
+            font.instance(pos, size * params.scale, glyph.atlas_pos - padding, size, color)
+        };
+        let outline = params.effects.outline.map_or(0.0, |outline| outline.width / params.scale);
+        // every shadow goes under every glyph
+        let mut shadows: Vec<_> = params.effects.shadow.iter()
+            .flat_map(|shadow| text_layout.glyphs.iter().map(|glyph| (page(glyph), InstanceBuffer {
+                outline_color: shadow.color,
+                effect: vec4(outline, shadow.softness / params.scale, spread, 0.0),
+                ..glyph_instance(glyph, shadow.offset, shadow.color)
+            })))
+            .collect();
+        shadows.sort_by_key(|(page, _)| *page);
+        let mut glyphs: Vec<_> = text_layout.glyphs.iter()
+            .map(|glyph| (page(glyph), InstanceBuffer {
+                outline_color: params.effects.outline.map_or(glyph.color, |outline| outline.color),
+                effect: vec4(outline, 0.0, spread, 0.0),
+                ..glyph_instance(glyph, vec2(0.0, 0.0), glyph.color)
+            }))
+            .collect();
+        glyphs.sort_by_key(|(page, _)| *page);
+        shadows.append(&mut glyphs);
+        shadows
+    }
+
+    fn create_page(&self, rc: &mut RenderContext, font_name: &str, size: [u32; 2], data: Option<&[u8]>, sampler: &wgpu::Sampler)
+            -> AtlasPage {
+        let descriptor = wgpu::TextureDescriptor {
+            label: Some(font_name),
+            size: wgpu::Extent3d {
+                width: size[0],
+                height: size[1],
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        };
+        let texture = match data {
+            Some(data) => rc.device.create_texture_with_data(&rc.queue, &descriptor, wgpu::util::TextureDataOrder::MipMajor, data),
+            None => rc.device.create_texture(&descriptor),
+        };
+        let view = texture.create_view(&Default::default());
+        let bind_group = rc.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(font_name),
+            layout: &self.bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: self.uniform_buf.as_entire_binding(),
+            }, wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&view),
+            }, wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            }],
+        });
+        AtlasPage { texture, bind_group }
+    }
+
+    // Shapes the text of cached fonts, rasterizes the glyphs it needs and
+    // copies the changed parts of the cache's pages to the GPU. Returns the
+    // shaped lines, which are empty for atlas fonts.
+    fn prepare_cache(&mut self, rc: &mut RenderContext, params: &RenderParams) -> Vec<Vec<shaping::ShapedRun>> {
+        let frame = self.frame;
+        let Some(font) = self.fonts.get_mut(&params.font) else { return vec![] };
+        let Glyphs::Cache(cache) = &mut font.glyphs else { return vec![] };
+        let span = layout::Span { text: &params.text, font: 0, color: params.color };
+        let lines = shaping::shape_spans(std::slice::from_ref(cache.as_ref()), &[span]);
+        cache.prepare_glyphs(frame, lines.iter().flatten().flat_map(|run| &run.glyphs).map(|glyph| glyph.glyph));
+        let uploads = cache.take_uploads();
+        let (page_count, page_size, name) = (cache.page_count(), cache.page_size(), cache.name.clone());
+        let sampler = font.sampler.clone();
+        let mut new_pages = vec![];
+        for _ in font.pages.len()..page_count {
+            new_pages.push(self.create_page(rc, &name, [page_size, page_size], None, &sampler));
+        }
+        let font = self.fonts.get_mut(&params.font).unwrap();
+        font.pages.append(&mut new_pages);
+        for upload in uploads {
+            rc.queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &font.pages[upload.page].texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d { x: upload.origin[0], y: upload.origin[1], z: 0 },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &upload.data,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(upload.size[0]),
+                    rows_per_image: None,
+                },
+                wgpu::Extent3d {
+                    width: upload.size[0],
+                    height: upload.size[1],
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        lines
+    }
+}
+
+impl FontData {
+    // `atlas_pos` and `atlas_size` are in atlas pixels
+    fn instance(&self, pos: Vec2, size: Vec2, atlas_pos: Vec2, atlas_size: Vec2, color: Vec4) -> InstanceBuffer {
+        InstanceBuffer {
+            matrix: Mat4::translate2d(pos) * Mat4::scale2d(size),
+            texture_pos: atlas_pos / self.image_size,
+            texture_scale: atlas_size / self.image_size,
+            color,
+            outline_color: color,
+            effect: vec4(0.0, 0.0, 0.0, 0.0),
+        }
+    }
+}
+
+impl rrs::Renderer for Renderer {
+    fn discriminant(&self) -> EntryDiscriminants {
+        EntryDiscriminants::Text
+    }
+
+    fn pre_render(&mut self, rc: &mut RenderContext, record: &Record, _: &Settings) {
+        self.frame += 1;
+        self.prepared.clear();
+        for entry in &record.entries {
+            if let Entry::Text(params) = entry {
+                let lines = self.prepare_cache(rc, params);
+                let instances = self.fonts.get(&params.font).map_or(vec![], |font| Self::layout(font, params, &lines));
+                self.prepared.push(instances);
+            }
+        }
+        // shaping can make more glyphs than chars, so the buffer is sized
+        // from the instances themselves
+        let new_count: usize = self.prepared.iter().map(Vec::len).sum();
+        if new_count > self.instance_buf_count {
+            self.instance_buf = rc.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: &vec![0; new_count * size_of::<InstanceBuffer>()].into_boxed_slice(),
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::VERTEX,
+            });
+            self.instance_buf_count = new_count;
+        }
+        self.current_buf = 0;
+        self.current_entry = 0;
+    }
+
+    fn render(&mut self, rc: &mut RenderContext, rpass: &mut wgpu::RenderPass, entry: &Entry, _: &Settings) {
+        let params = match entry {
+            Entry::Text(p) => p,
+            _ => panic!("Failed to call correct renderer!"),
+        };
+        // text entries are never culled, so they arrive in pre_render's order
+        let instances = self.prepared.get_mut(self.current_entry).map(std::mem::take).unwrap_or_default();
+        self.current_entry += 1;
+        let Some(font) = self.fonts.get(&params.font) else { return };
+        if instances.is_empty() {
+            return;
+        }
+
+        let buf_index = self.current_buf;
+        self.current_buf += instances.len() as u32;
+        let offset: u64 = (size_of::<InstanceBuffer>() * (buf_index as usize)).try_into().unwrap();
+        let buffer: Vec<InstanceBuffer> = instances.iter().map(|(_, instance)| *instance).collect();
+        rc.queue.write_buffer(&self.instance_buf, offset, bytemuck::cast_slice(&buffer));
+        rpass.set_pipeline(match font.distance_field {
+            Some(_) => &self.sdf_pipeline,
+            None => &self.pipeline,
+        });
+        rpass.set_vertex_buffer(0, self.instance_buf.slice(..));
+        // one draw for each run of instances on the same page
+        let mut start = buf_index;
+        for run in instances.chunk_by(|(a, _), (b, _)| a == b) {
+            let Some(page) = font.pages.get(run[0].0) else { continue };
+            rpass.set_bind_group(0, &page.bind_group, &[]);
+            rpass.draw(0..6, start..start + run.len() as u32);
+            start += run.len() as u32;
+        }
+    }
+
+    fn post_render(&mut self, rc: &mut RenderContext, _: &Record, settings: &Settings) {
+        self.current_buf = 0;
+        self.prepared.clear();
+        rc.queue.write_buffer(&self.uniform_buf, 0, settings.projection.as_ref());
+    }
+
+    fn load<'a>(&mut self, rc: &mut RenderContext, update: Update<'a>) -> Update<'a> {
+        match update {
+            Update::Args(rrs::UpdateArgs::Text(args)) => {
+                let filter = match &args {
+                    UpdateArgs::Atlas(_, filter) | UpdateArgs::Cache(_, filter) => *filter,
+                };
+                let sampler = rc.device.create_sampler(&wgpu::SamplerDescriptor {
+                    address_mode_u: wgpu::AddressMode::ClampToEdge,
+                    address_mode_v: wgpu::AddressMode::ClampToEdge,
+                    address_mode_w: wgpu::AddressMode::ClampToEdge,
+                    mag_filter: filter,
+                    min_filter: filter,
+                    mipmap_filter: wgpu::FilterMode::Nearest,
+                    ..Default::default()
+                });
+                let font = match args {
+                    UpdateArgs::Atlas(font_info, _) => {
+                        let [width, height] = font_info.image_size;
+                        let page = self.create_page(rc, &font_info.name, font_info.image_size, Some(&font_info.image_buffer), &sampler);
+                        FontData {
+                            glyphs: Glyphs::Atlas(font_info.get_metrics_info()),
+                            pages: vec![page],
+                            sampler,
+                            image_size: vec2(width as f32, height as f32),
+                            distance_field: font_info.distance_field,
+                        }
+                    },
+                    UpdateArgs::Cache(cache, _) => {
+                        let size = cache.page_size() as f32;
+                        FontData {
+                            glyphs: Glyphs::Cache(cache),
+                            pages: vec![],
+                            sampler,
+                            image_size: vec2(size, size),
+                            distance_field: None,
+                        }
+                    },
+                };
+                let handle = self.fonts.put(font);
+                Update::Return(rrs::UpdateReturn::Text(UpdateReturn(handle)))
+            },
+            _ => panic!("Invalid update for text renderer: {:?}", update),
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::mat::vec2;
+
+use super::{shaping::{Shaper, ShapingFont}, BaseFontInfoContainer, FontMetricsInfo, GlyphMetrics};
+
+// Rasterizes glyphs the first time they're drawn and packs them into square
+// atlas pages on shelves, one shelf per row of similarly tall glyphs. When
+// every page is full the least recently used glyphs are evicted, except for
+// the ones drawn in the current frame. Glyphs are keyed by their index in the
+// font, so shaped text can use glyphs that no char maps to.
+pub struct GlyphCache {
+    font: fontdue::Font,
+    shaper: Shaper,
+    font_size: f32,
+    height: f32,
+    ascent: f32,
+    page_size: u32,
+    max_pages: usize,
+    pages: Vec<Page>,
+    glyphs: HashMap<u16, CachedGlyph>,
+    uploads: Vec<AtlasUpload>,
+    pub not_found_char: Option<char>,
+    pub ignore_chars: HashSet<char>,
+    pub name: String,
+}
+
+// A region of an atlas page that needs to be copied to the GPU. `data` is
+// one byte per pixel, row by row.
+#[derive(Debug, Clone)]
+pub struct AtlasUpload {
+    pub page: usize,
+    pub origin: [u32; 2],
+    pub size: [u32; 2],
+    pub data: Vec<u8>,
+}
+
+struct CachedGlyph {
+    metrics: GlyphMetrics,
+    // None for glyphs without pixels, like spaces
+    slot: Option<Slot>,
+    last_used: u64,
+}
+
+#[derive(Clone, Copy)]
+struct Slot {
+    page: usize,
+    shelf: usize,
+    x: u32,
+    width: u32,
+}
+
+#[derive(Default)]
+struct Page {
+    shelves: Vec<Shelf>,
+    bottom: u32,
+}
+
+struct Shelf {
+    y: u32,
+    height: u32,
+    // everything right of `end` is free
+    end: u32,
+    // freed slots left of `end`, as (x, width) sorted by x
+    free: Vec<(u32, u32)>,
+}
+
+impl Shelf {
+    fn take(&mut self, width: u32, page_size: u32) -> Option<u32> {
+        if let Some(index) = self.free.iter().position(|(_, free)| *free >= width) {
+            let (x, free) = self.free[index];
+            if free == width {
+                self.free.remove(index);
+            } else {
+                self.free[index] = (x + width, free - width);
+            }
+            return Some(x);
+        }
+        (self.end + width <= page_size).then(|| {
+            self.end += width;
+            self.end - width
+        })
+    }
+
+    fn release(&mut self, x: u32, width: u32) {
+        let index = self.free.partition_point(|(free, _)| *free < x);
+        self.free.insert(index, (x, width));
+        // merge neighbouring slots
+        let mut merged: Vec<(u32, u32)> = vec![];
+        for (x, width) in self.free.drain(..) {
+            match merged.last_mut() {
+                Some((last, last_width)) if *last + *last_width == x => *last_width += width,
+                _ => merged.push((x, width)),
+            }
+        }
+        if let Some((x, width)) = merged.last().copied() {
+            if x + width == self.end {
+                merged.pop();
+                self.end = x;
+            }
+        }
+        self.free = merged;
+    }
+}
+
+impl std::fmt::Debug for GlyphCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GlyphCache")
+            .field("name", &self.name)
+            .field("font_size", &self.font_size)
+            .field("pages", &self.pages.len())
+            .field("glyphs", &self.glyphs.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl GlyphCache {
+    pub fn new(bytes: &[u8], font_size: f32, page_size: u32, max_pages: usize, name: String) -> Result<GlyphCache, String> {
+        let font = super::load_font(bytes, font_size)?;
+        let shaper = Shaper::new(bytes)?;
+        let (height, ascent) = font.horizontal_line_metrics(font_size)
+            .map_or((font_size, font_size), |metrics| (metrics.new_line_size, metrics.ascent));
+        Ok(GlyphCache {
+            font,
+            shaper,
+            font_size,
+            height,
+            ascent,
+            page_size,
+            max_pages: max_pages.max(1),
+            pages: vec![],
+            glyphs: HashMap::new(),
+            uploads: vec![],
+            not_found_char: None,
+            ignore_chars: ['\r'].into(),
+            name,
+        })
+    }
+
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    // Whether the glyph `c` maps to is cached, not counting not_found_char
+    pub fn contains(&self, c: char) -> bool {
+        self.glyphs.contains_key(&self.font.lookup_glyph_index(c))
+    }
+
+    // The atlas page the glyph drawn for `c` is on
+    pub fn page(&self, c: char) -> Option<usize> {
+        self.lookup(c).and_then(|glyph| glyph.slot).map(|slot| slot.page)
+    }
+
+    pub fn glyph_page(&self, glyph: u16) -> Option<usize> {
+        self.glyphs.get(&glyph).and_then(|glyph| glyph.slot).map(|slot| slot.page)
+    }
+
+    // Makes sure every glyph in `text` is in the atlas and marks them as used
+    // in `frame`. Glyphs that can't fit without evicting others from the same
+    // frame are left out.
+    pub fn prepare(&mut self, frame: u64, text: &str) {
+        for c in text.chars() {
+            if c == '\n' || self.ignore_chars.contains(&c) {
+                continue;
+            }
+            let glyph = match (self.font.lookup_glyph_index(c), self.not_found_char) {
+                (0, Some(not_found)) => self.font.lookup_glyph_index(not_found),
+                (glyph, _) => glyph,
+            };
+            self.prepare_glyph(frame, glyph);
+        }
+    }
+
+    // Like prepare, for glyph indices that came out of shaping
+    pub fn prepare_glyphs(&mut self, frame: u64, glyphs: impl IntoIterator<Item = u16>) {
+        for glyph in glyphs {
+            self.prepare_glyph(frame, glyph);
+        }
+    }
+
+    fn prepare_glyph(&mut self, frame: u64, glyph: u16) {
+        match self.glyphs.get_mut(&glyph) {
+            Some(cached) => cached.last_used = frame,
+            None => self.rasterize(frame, glyph),
+        }
+    }
+
+    // Writes made to the pages since the last call
+    pub fn take_uploads(&mut self) -> Vec<AtlasUpload> {
+        std::mem::take(&mut self.uploads)
+    }
+
+    fn lookup(&self, c: char) -> Option<&CachedGlyph> {
+        if self.ignore_chars.contains(&c) {
+            return None
+        }
+        match (self.font.lookup_glyph_index(c), self.not_found_char) {
+            (0, Some(not_found)) => self.glyphs.get(&self.font.lookup_glyph_index(not_found)),
+            (glyph, _) => self.glyphs.get(&glyph),
+        }
+    }
+
+    fn rasterize(&mut self, frame: u64, glyph: u16) {
+        let (metrics, bitmap) = self.font.rasterize_indexed(glyph, self.font_size);
+        let mut glyph_metrics = GlyphMetrics {
+            glyph_pos: vec2(0.0, 0.0),
+            glyph_size: vec2(metrics.width as f32, metrics.height as f32),
+            advance: metrics.advance_width,
+            lsb: metrics.xmin as f32,
+            tsb: (metrics.ymin + metrics.height as i32) as f32,
+        };
+        let slot = if metrics.width == 0 || metrics.height == 0 {
+            None
+        } else {
+            // 1px of padding on the bottom and right like the static atlas,
+            // cleared along with the glyph since slots get reused
+            let (width, height) = (metrics.width as u32 + 1, metrics.height as u32 + 1);
+            let Some((slot, y)) = self.allocate(frame, width, height) else { return };
+            let mut data = vec![0; (width * height) as usize];
+            for (y, row) in bitmap.chunks(metrics.width).enumerate() {
+                let start = y * width as usize;
+                data[start..start + metrics.width].copy_from_slice(row);
+            }
+            self.uploads.push(AtlasUpload {
+                page: slot.page,
+                origin: [slot.x, y],
+                size: [width, height],
+                data,
+            });
+            glyph_metrics.glyph_pos = vec2(slot.x as f32, y as f32);
+            Some(slot)
+        };
+        self.glyphs.insert(glyph, CachedGlyph { metrics: glyph_metrics, slot, last_used: frame });
+    }
+
+    // Finds room for a glyph, adding pages and then evicting as needed.
+    // Returns the slot and the y of its shelf.
+    fn allocate(&mut self, frame: u64, width: u32, height: u32) -> Option<(Slot, u32)> {
+        if width > self.page_size || height > self.page_size {
+            return None;
+        }
+        loop {
+            if let Some(found) = self.find_space(width, height) {
+                return Some(found);
+            }
+            if self.pages.len() < self.max_pages {
+                self.pages.push(Page::default());
+            } else if !self.evict(frame) {
+                return None;
+            }
+        }
+    }
+
+    fn find_space(&mut self, width: u32, height: u32) -> Option<(Slot, u32)> {
+        let page_size = self.page_size;
+        for (page_index, page) in self.pages.iter_mut().enumerate() {
+            // the shortest shelf the glyph fits on wastes the least space
+            let mut shelves: Vec<usize> = (0..page.shelves.len())
+                .filter(|shelf| page.shelves[*shelf].height >= height)
+                .collect();
+            shelves.sort_by_key(|shelf| page.shelves[*shelf].height);
+            for shelf_index in shelves {
+                let shelf = &mut page.shelves[shelf_index];
+                if let Some(x) = shelf.take(width, page_size) {
+                    return Some((Slot { page: page_index, shelf: shelf_index, x, width }, shelf.y));
+                }
+            }
+            if page.bottom + height <= page_size {
+                page.shelves.push(Shelf { y: page.bottom, height, end: width, free: vec![] });
+                page.bottom += height;
+                return Some((Slot { page: page_index, shelf: page.shelves.len() - 1, x: 0, width }, page.bottom - height));
+            }
+        }
+        None
+    }
+
+    // Evicts the least recently used glyph from before `frame`
+    fn evict(&mut self, frame: u64) -> bool {
+        let oldest = self.glyphs.iter()
+            .filter(|(_, glyph)| glyph.slot.is_some() && glyph.last_used < frame)
+            .min_by_key(|(_, glyph)| glyph.last_used)
+            .map(|(glyph, _)| *glyph);
+        let Some(glyph) = oldest else { return false };
+        let slot = self.glyphs.remove(&glyph).and_then(|glyph| glyph.slot).unwrap();
+        let page = &mut self.pages[slot.page];
+        page.shelves[slot.shelf].release(slot.x, slot.width);
+        // empty shelves at the bottom give their height back for taller glyphs
+        while page.shelves.last().is_some_and(|shelf| shelf.end == 0) {
+            page.bottom = page.shelves.pop().unwrap().y;
+        }
+        true
+    }
+}
+
+impl BaseFontInfoContainer for GlyphCache {
+    fn line_height(&self) -> f32 {
+        self.height
+    }
+
+    fn ascent(&self) -> f32 {
+        self.ascent
+    }
+
+    fn font_size(&self) -> f32 {
+        self.font_size
+    }
+
+    fn kerning(&self, left: char, right: char) -> f32 {
+        self.font.horizontal_kern(left, right, self.font_size).unwrap_or(0.0)
+    }
+
+    // only glyphs that have been prepared
+    fn get_metrics<'a>(&'a self, c: &char) -> Option<&'a GlyphMetrics> {
+        self.lookup(*c).map(|glyph| &glyph.metrics)
+    }
+
+    fn get_glyph_metrics(&self, glyph: u16) -> Option<&GlyphMetrics> {
+        self.glyphs.get(&glyph).map(|glyph| &glyph.metrics)
+    }
+
+    // a snapshot of the chars cached so far, without kerning
+    fn get_metrics_info(&self) -> FontMetricsInfo {
+        FontMetricsInfo {
+            char_data: self.font.chars().iter()
+                .filter_map(|(c, glyph)| Some((*c, self.glyphs.get(&glyph.get())?.metrics.clone())))
+                .collect(),
+            font_size: self.font_size,
+            height: self.height,
+            ascent: self.ascent,
+            kerning: HashMap::new(),
+            not_found_char: self.not_found_char,
+            ignore_chars: self.ignore_chars.clone(),
+        }
+    }
+
+    fn is_char_valid(&self, c: &char) -> bool {
+        if self.ignore_chars.contains(c) {
+            return false
+        }
+        self.font.lookup_glyph_index(*c) != 0
+    }
+}
+
+impl ShapingFont for GlyphCache {
+    fn shaper(&self) -> &Shaper {
+        &self.shaper
+    }
+}
@@ -0,0 +1,73 @@
+use super::{layout::Span, BaseFontInfoContainer};
+
+// Fonts with fallback chains, e.g. a Latin font falling back to a CJK font
+// and then an emoji font. Spans name the font they'd like and `split` moves
+// each char to the first font in that font's chain that has it.
+pub struct FontCollection<F: BaseFontInfoContainer> {
+    pub fonts: Vec<F>,
+    fallbacks: Vec<Vec<usize>>,
+}
+
+impl<F: BaseFontInfoContainer> Default for FontCollection<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: BaseFontInfoContainer> FontCollection<F> {
+    pub fn new() -> FontCollection<F> {
+        FontCollection {
+            fonts: vec![],
+            fallbacks: vec![],
+        }
+    }
+
+    // Returns the font's index, for Span::font
+    pub fn add(&mut self, font: F) -> usize {
+        self.fonts.push(font);
+        self.fallbacks.push(vec![]);
+        self.fonts.len() - 1
+    }
+
+    // Fonts to try, in order, when `font` is missing a char
+    pub fn set_fallbacks(&mut self, font: usize, fallbacks: &[usize]) {
+        self.fallbacks[font] = fallbacks.iter().copied().filter(|fallback| *fallback != font).collect();
+    }
+
+    pub fn fallbacks(&self, font: usize) -> &[usize] {
+        &self.fallbacks[font]
+    }
+
+    // The first font in the chain starting at `font` that has `c`, or `font`
+    // itself when none do
+    pub fn font_for(&self, font: usize, c: char) -> usize {
+        std::iter::once(font)
+            .chain(self.fallbacks[font].iter().copied())
+            .find(|font| self.fonts[*font].is_char_valid(&c))
+            .unwrap_or(font)
+    }
+
+    // Splits spans into runs that each use a single font. Whitespace and
+    // line breaks stay in the current run when its font has them, so runs
+    // aren't broken up between words.
+    pub fn split<'a>(&self, spans: &[Span<'a>]) -> Vec<Span<'a>> {
+        let mut runs = vec![];
+        for span in spans {
+            let mut start = 0;
+            let mut current: Option<usize> = None;
+            for (index, c) in span.text.char_indices() {
+                let font = match current {
+                    Some(current) if c.is_whitespace() && (c == '\n' || self.fonts[current].is_char_valid(&c)) => current,
+                    _ => self.font_for(span.font, c),
+                };
+                if current.is_some_and(|current| current != font) {
+                    runs.push(Span { text: &span.text[start..index], font: current.unwrap(), ..*span });
+                    start = index;
+                }
+                current = Some(font);
+            }
+            runs.push(Span { text: &span.text[start..], font: current.unwrap_or(span.font), ..*span });
+        }
+        runs
+    }
+}
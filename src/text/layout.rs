@@ -0,0 +1,402 @@
+use std::ops::Range;
+
+use unicode_bidi::{BidiInfo, Level};
+
+use crate::mat::{vec2, Vec2, Vec4};
+
+use super::{shaping::ShapedRun, BaseFontInfoContainer};
+
+// Lays out spans of text into positioned glyph quads on the CPU. Positions are
+// in pixels from the top left corner of the text box, with y going down like
+// Mat4::ortho.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+    // stretches the spaces of wrapped lines to fill the width, the last line
+    // of each paragraph stays left aligned
+    Justify,
+}
+
+#[derive(Debug, Clone)]
+pub struct LayoutSettings {
+    // wrap at word boundaries past this width, words wider than it are split
+    pub max_width: Option<f32>,
+    pub align: Align,
+    // multiplier on each font's line height
+    pub line_spacing: f32,
+    pub kerning: bool,
+}
+
+impl Default for LayoutSettings {
+    fn default() -> Self {
+        LayoutSettings {
+            max_width: None,
+            align: Align::Left,
+            line_spacing: 1.0,
+            kerning: true,
+        }
+    }
+}
+
+// A run of text drawn with one font and color. `font` indexes the fonts
+// passed to `layout`, one per size.
+#[derive(Debug, Clone, Copy)]
+pub struct Span<'a> {
+    pub text: &'a str,
+    pub font: usize,
+    pub color: Vec4,
+}
+
+#[derive(Debug, Clone)]
+pub struct PositionedGlyph {
+    pub c: char,
+    // glyph index for shaped text, which doesn't map back to chars
+    pub glyph: Option<u16>,
+    pub span: usize,
+    pub font: usize,
+    // top left corner of the quad
+    pub position: Vec2,
+    pub size: Vec2,
+    // top left corner in the font's atlas, in pixels
+    pub atlas_pos: Vec2,
+    pub color: Vec4,
+}
+
+#[derive(Debug, Clone)]
+pub struct LayoutLine {
+    // indices into Layout::glyphs
+    pub glyphs: Range<usize>,
+    pub top: f32,
+    pub baseline: f32,
+    pub height: f32,
+    // horizontal offset from alignment
+    pub offset: f32,
+    pub width: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Layout {
+    pub glyphs: Vec<PositionedGlyph>,
+    pub lines: Vec<LayoutLine>,
+    pub width: f32,
+    pub height: f32,
+}
+
+struct Item {
+    c: char,
+    glyph: Option<u16>,
+    span: usize,
+    font: usize,
+    advance: f32,
+    lsb: f32,
+    tsb: f32,
+    size: Vec2,
+    atlas_pos: Vec2,
+    color: Vec4,
+    // bidi embedding level
+    level: u8,
+}
+
+impl Item {
+    fn is_space(&self) -> bool {
+        self.c == ' ' || self.c == '\t'
+    }
+}
+
+#[derive(Default)]
+struct PendingLine {
+    // item index and pen position
+    items: Vec<(usize, f32)>,
+    pen: f32,
+    // font of the last item, for the height of empty lines
+    font: Option<usize>,
+}
+
+struct Builder<'a, F: BaseFontInfoContainer> {
+    fonts: &'a [F],
+    items: &'a [Item],
+    settings: &'a LayoutSettings,
+    line: PendingLine,
+    // finished lines and whether they ended by wrapping
+    lines: Vec<(PendingLine, bool)>,
+}
+
+impl<F: BaseFontInfoContainer> Builder<'_, F> {
+    // kerning before the item if it was pushed now
+    fn kerning(&self, index: usize) -> f32 {
+        self.kerning_after(self.line.items.last().map(|(previous, _)| *previous), index)
+    }
+
+    fn kerning_after(&self, previous: Option<usize>, index: usize) -> f32 {
+        let Some(previous) = previous else { return 0.0 };
+        let (previous, item) = (&self.items[previous], &self.items[index]);
+        // shaped text is already kerned
+        if !self.settings.kerning || previous.font != item.font || item.glyph.is_some() || previous.glyph.is_some() {
+            return 0.0;
+        }
+        self.fonts[item.font].kerning(previous.c, item.c)
+    }
+
+    fn push(&mut self, index: usize) {
+        let x = self.line.pen + self.kerning(index);
+        self.line.items.push((index, x));
+        self.line.pen = x + self.items[index].advance;
+        self.line.font = Some(self.items[index].font);
+    }
+
+    // Right edge of the item's ink if it was pushed now
+    fn right(&self, index: usize) -> f32 {
+        let item = &self.items[index];
+        self.line.pen + self.kerning(index) + item.lsb + item.size.x
+    }
+
+    fn has_word(&self) -> bool {
+        self.line.items.iter().any(|(index, _)| !self.items[*index].is_space())
+    }
+
+    fn finish(&mut self, wrapped: bool) {
+        let font = self.line.font;
+        let line = std::mem::replace(&mut self.line, PendingLine { font, ..Default::default() });
+        self.lines.push((line, wrapped));
+    }
+
+    fn word(&mut self, range: Range<usize>) {
+        let Some(max_width) = self.settings.max_width else {
+            range.for_each(|index| self.push(index));
+            return;
+        };
+        // measure the word as if it was placed on the current line
+        let mut pen = self.line.pen;
+        let mut right = pen;
+        let mut previous = self.line.items.last().map(|(index, _)| *index);
+        for index in range.clone() {
+            let item = &self.items[index];
+            let kern = self.kerning_after(previous, index);
+            right = right.max(pen + kern + item.lsb + item.size.x);
+            pen += kern + item.advance;
+            previous = Some(index);
+        }
+        if right > max_width && self.has_word() {
+            self.finish(true);
+        }
+        for index in range {
+            // words that don't fit on a line of their own get split
+            if self.has_word() && self.right(index) > max_width {
+                self.finish(true);
+            }
+            self.push(index);
+        }
+    }
+
+    fn spaces(&mut self, range: Range<usize>) {
+        // spaces at the start of a wrapped line are dropped
+        if self.line.items.is_empty() && self.lines.last().is_some_and(|(_, wrapped)| *wrapped) {
+            return;
+        }
+        range.for_each(|index| self.push(index));
+    }
+}
+
+pub fn layout<F: BaseFontInfoContainer>(fonts: &[F], spans: &[Span], settings: &LayoutSettings) -> Layout {
+    let mut items = vec![];
+    let mut breaks = vec![];
+    for (span_index, span) in spans.iter().enumerate() {
+        let font = &fonts[span.font];
+        for c in span.text.chars() {
+            if c == '\n' {
+                breaks.push(items.len());
+                continue;
+            }
+            let Some(metrics) = font.get_metrics(&c) else { continue };
+            items.push(Item {
+                c,
+                glyph: None,
+                span: span_index,
+                font: span.font,
+                advance: metrics.advance,
+                lsb: metrics.lsb,
+                tsb: metrics.tsb,
+                size: metrics.glyph_size,
+                atlas_pos: metrics.glyph_pos,
+                color: span.color,
+                level: 0,
+            });
+        }
+    }
+    arrange(fonts, &items, breaks, spans.first().map(|span| span.font), settings)
+}
+
+// Lays out the lines from shaping::shape_spans. Glyph metrics come from
+// BaseFontInfoContainer::get_glyph_metrics, glyphs without them still advance
+// the pen but aren't drawn. Lines wrap in logical order and are then put in
+// visual order, so right to left paragraphs break from the right.
+pub fn layout_shaped<F: BaseFontInfoContainer>(fonts: &[F], lines: &[Vec<ShapedRun>], settings: &LayoutSettings) -> Layout {
+    let mut items = vec![];
+    let mut breaks = vec![];
+    for line in lines {
+        if !items.is_empty() || !breaks.is_empty() {
+            breaks.push(items.len());
+        }
+        for run in line {
+            // right to left runs are shaped in visual order
+            let glyphs: Vec<_> = match run.rtl {
+                true => run.glyphs.iter().rev().collect(),
+                false => run.glyphs.iter().collect(),
+            };
+            for glyph in glyphs {
+                let metrics = fonts[run.font].get_glyph_metrics(glyph.glyph);
+                items.push(Item {
+                    c: glyph.c,
+                    glyph: Some(glyph.glyph),
+                    span: run.span,
+                    font: run.font,
+                    advance: glyph.advance,
+                    lsb: metrics.map_or(0.0, |metrics| metrics.lsb) + glyph.offset.x,
+                    tsb: metrics.map_or(0.0, |metrics| metrics.tsb) + glyph.offset.y,
+                    size: metrics.map_or(vec2(0.0, 0.0), |metrics| metrics.glyph_size),
+                    atlas_pos: metrics.map_or(vec2(0.0, 0.0), |metrics| metrics.glyph_pos),
+                    color: run.color,
+                    level: run.level,
+                });
+            }
+        }
+    }
+    let first_font = lines.iter().flatten().next().map(|run| run.font);
+    arrange(fonts, &items, breaks, first_font, settings)
+}
+
+// Puts a wrapped line of bidi text in visual order and moves the pen along
+// it again. Trailing spaces stay at the end of the line.
+fn reorder(items: &[Item], line: &mut PendingLine) {
+    if line.items.iter().all(|(index, _)| items[*index].level == 0) {
+        return;
+    }
+    let end = line.items.iter().rposition(|(index, _)| !items[*index].is_space()).map_or(0, |last| last + 1);
+    let levels: Vec<Level> = line.items[..end].iter()
+        .map(|(index, _)| Level::new(items[*index].level).unwrap_or_else(|_| Level::ltr()))
+        .collect();
+    let logical = std::mem::take(&mut line.items);
+    line.pen = 0.0;
+    for position in BidiInfo::reorder_visual(&levels).into_iter().chain(end..logical.len()) {
+        let index = logical[position].0;
+        line.items.push((index, line.pen));
+        line.pen += items[index].advance;
+    }
+}
+
+// `first_font` sets the height of lines before the first item
+fn arrange<F: BaseFontInfoContainer>(
+    fonts: &[F],
+    items: &[Item],
+    breaks: Vec<usize>,
+    first_font: Option<usize>,
+    settings: &LayoutSettings,
+) -> Layout {
+    let mut builder = Builder {
+        fonts,
+        items,
+        settings,
+        line: PendingLine { font: first_font, ..Default::default() },
+        lines: vec![],
+    };
+    let mut start = 0;
+    let mut breaks = breaks.into_iter().peekable();
+    while start < items.len() || breaks.peek().is_some() {
+        // hard line breaks come before the item they were found in front of
+        if breaks.peek() == Some(&start) {
+            breaks.next();
+            builder.finish(false);
+            continue;
+        }
+        let next_break = breaks.peek().copied().unwrap_or(items.len());
+        let space = items[start].is_space();
+        let end = (start..next_break).find(|index| items[*index].is_space() != space).unwrap_or(next_break);
+        if space {
+            builder.spaces(start..end);
+        } else {
+            builder.word(start..end);
+        }
+        start = end;
+    }
+    builder.finish(false);
+    let mut lines = builder.lines;
+    for (line, _) in &mut lines {
+        reorder(items, line);
+    }
+
+    // ink width of each line, trailing spaces don't count
+    let line_width = |line: &PendingLine| line.items.iter()
+        .filter(|(index, _)| !items[*index].is_space())
+        .map(|(index, x)| x + items[*index].lsb + items[*index].size.x)
+        .fold(0.0, f32::max);
+    let widest = lines.iter().map(|(line, _)| line_width(line)).fold(0.0, f32::max);
+    let box_width = settings.max_width.unwrap_or(widest);
+
+    let mut result = Layout {
+        width: box_width,
+        ..Default::default()
+    };
+    let mut top = 0.0;
+    for (line, wrapped) in &lines {
+        let fonts_used = || line.items.iter().map(|(index, _)| items[*index].font).chain(line.font);
+        let height = fonts_used().map(|font| fonts[font].line_height()).fold(0.0, f32::max) * settings.line_spacing;
+        let ascent = fonts_used().map(|font| fonts[font].ascent()).fold(0.0, f32::max);
+        let width = line_width(line);
+        let offset = match settings.align {
+            Align::Left | Align::Justify => 0.0,
+            Align::Center => (box_width - width) / 2.0,
+            Align::Right => box_width - width,
+        };
+        // spaces between words, not the trailing ones
+        let last_word = line.items.iter().rposition(|(index, _)| !items[*index].is_space());
+        let gaps = line.items.iter().enumerate()
+            .filter(|(position, (index, _))| items[*index].is_space() && last_word.is_some_and(|last| *position < last))
+            .count();
+        let stretch = if settings.align == Align::Justify && *wrapped && gaps > 0 {
+            (box_width - width).max(0.0) / gaps as f32
+        } else {
+            0.0
+        };
+
+        let baseline = top + ascent;
+        let first = result.glyphs.len();
+        let mut extra = 0.0;
+        for (position, (index, x)) in line.items.iter().enumerate() {
+            let item = &items[*index];
+            if item.is_space() {
+                if last_word.is_some_and(|last| position < last) {
+                    extra += stretch;
+                }
+                continue;
+            }
+            if item.size.x <= 0.0 || item.size.y <= 0.0 {
+                continue;
+            }
+            result.glyphs.push(PositionedGlyph {
+                c: item.c,
+                glyph: item.glyph,
+                span: item.span,
+                font: item.font,
+                position: vec2(offset + x + extra + item.lsb, baseline - item.tsb),
+                size: item.size,
+                atlas_pos: item.atlas_pos,
+                color: item.color,
+            });
+        }
+        result.lines.push(LayoutLine {
+            glyphs: first..result.glyphs.len(),
+            top,
+            baseline,
+            height,
+            offset,
+            width: if stretch > 0.0 { box_width } else { width },
+        });
+        top += height;
+    }
+    result.height = top;
+    result
+}
@@ -0,0 +1,94 @@
+// Signed distance fields for glyph atlases. Values are stored in one byte,
+// 0.5 is the glyph's edge, higher is inside, and the range covers `spread`
+// pixels to each side of the edge.
+
+const INF: f32 = 1e20;
+
+// Turns a coverage bitmap into a distance field padded by `spread` pixels on
+// every side, so the result is (width + 2 * spread) x (height + 2 * spread)
+pub fn distance_field(coverage: &[u8], width: usize, height: usize, spread: usize) -> Vec<u8> {
+    let padded_width = width + spread * 2;
+    let padded_height = height + spread * 2;
+    let inside = |x: usize, y: usize| {
+        x >= spread && y >= spread && x < width + spread && y < height + spread
+            && coverage[(y - spread) * width + x - spread] >= 128
+    };
+    // squared distance to the closest pixel of the other kind
+    let mut to_inside = vec![INF; padded_width * padded_height];
+    let mut to_outside = vec![INF; padded_width * padded_height];
+    for y in 0..padded_height {
+        for x in 0..padded_width {
+            if inside(x, y) {
+                to_inside[y * padded_width + x] = 0.0;
+            } else {
+                to_outside[y * padded_width + x] = 0.0;
+            }
+        }
+    }
+    transform(&mut to_inside, padded_width, padded_height);
+    transform(&mut to_outside, padded_width, padded_height);
+
+    let spread = spread.max(1) as f32;
+    to_inside.iter().zip(&to_outside)
+        .map(|(to_inside, to_outside)| {
+            // pixel centers are half a pixel from the edge between them
+            let distance = if *to_inside == 0.0 {
+                to_outside.sqrt() - 0.5
+            } else {
+                0.5 - to_inside.sqrt()
+            };
+            ((0.5 + distance / (spread * 2.0)).clamp(0.0, 1.0) * 255.0).round() as u8
+        })
+        .collect()
+}
+
+// Felzenszwalb and Huttenlocher's squared euclidean distance transform, done
+// on columns then rows
+fn transform(grid: &mut [f32], width: usize, height: usize) {
+    let mut line = vec![0.0; width.max(height)];
+    let mut result = vec![0.0; width.max(height)];
+    for x in 0..width {
+        for y in 0..height {
+            line[y] = grid[y * width + x];
+        }
+        transform_line(&line[..height], &mut result[..height]);
+        for y in 0..height {
+            grid[y * width + x] = result[y];
+        }
+    }
+    for y in 0..height {
+        line[..width].copy_from_slice(&grid[y * width..(y + 1) * width]);
+        transform_line(&line[..width], &mut result[..width]);
+        grid[y * width..(y + 1) * width].copy_from_slice(&result[..width]);
+    }
+}
+
+// lower envelope of the parabolas rooted at each sample
+fn transform_line(f: &[f32], d: &mut [f32]) {
+    let n = f.len();
+    let mut v = vec![0; n];
+    let mut z = vec![0.0; n + 1];
+    let mut k = 0;
+    z[0] = -INF;
+    z[1] = INF;
+    for q in 1..n {
+        let intersect = |p: usize| ((f[q] + (q * q) as f32) - (f[p] + (p * p) as f32)) / (2.0 * q as f32 - 2.0 * p as f32);
+        let mut s = intersect(v[k]);
+        while s <= z[k] {
+            k -= 1;
+            s = intersect(v[k]);
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = INF;
+    }
+    k = 0;
+    for (q, d) in d.iter_mut().enumerate() {
+        while z[k + 1] < q as f32 {
+            k += 1;
+        }
+        let offset = q as f32 - v[k] as f32;
+        *d = offset * offset + f[v[k]];
+    }
+}
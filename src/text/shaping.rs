@@ -0,0 +1,147 @@
+use std::ops::Range;
+
+use unicode_bidi::BidiInfo;
+
+use crate::mat::{vec2, Vec2, Vec4};
+
+use super::{layout::Span, BaseFontInfoContainer};
+
+// Shapes text with rustybuzz, which handles ligatures, contextual forms and
+// mark placement for complex scripts. Holds a copy of the font file since the
+// rustybuzz face borrows it.
+pub struct Shaper {
+    data: Box<[u8]>,
+}
+
+// A shaped glyph, measured in pixels. `cluster` is the byte offset into the
+// span's text of the first char the glyph was made from, and `offset` moves
+// the glyph without moving the pen, with y going up like font units.
+#[derive(Debug, Clone)]
+pub struct ShapedGlyph {
+    pub glyph: u16,
+    pub c: char,
+    pub cluster: usize,
+    pub advance: f32,
+    pub offset: Vec2,
+}
+
+// Glyphs from one span and font, in visual order. `level` is the bidi
+// embedding level, odd for right to left text.
+#[derive(Debug, Clone)]
+pub struct ShapedRun {
+    pub span: usize,
+    pub font: usize,
+    pub color: Vec4,
+    pub rtl: bool,
+    pub level: u8,
+    pub glyphs: Vec<ShapedGlyph>,
+}
+
+// Fonts whose glyphs can be looked up by index, see
+// BaseFontInfoContainer::get_glyph_metrics
+pub trait ShapingFont: BaseFontInfoContainer {
+    fn shaper(&self) -> &Shaper;
+}
+
+impl std::fmt::Debug for Shaper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Shaper").finish_non_exhaustive()
+    }
+}
+
+impl Shaper {
+    pub fn new(bytes: &[u8]) -> Result<Shaper, String> {
+        rustybuzz::Face::from_slice(bytes, 0).ok_or("Could not parse font for shaping")?;
+        Ok(Shaper { data: bytes.into() })
+    }
+
+    // Parses the font, so shaping several runs should share one face
+    pub fn face(&self) -> rustybuzz::Face<'_> {
+        rustybuzz::Face::from_slice(&self.data, 0).unwrap()
+    }
+
+    pub fn shape(&self, text: &str, font_size: f32, rtl: bool) -> Vec<ShapedGlyph> {
+        Self::shape_with(&self.face(), text, font_size, rtl)
+    }
+
+    // Glyphs come out in visual order, so right to left text is reversed
+    pub fn shape_with(face: &rustybuzz::Face, text: &str, font_size: f32, rtl: bool) -> Vec<ShapedGlyph> {
+        let scale = font_size / face.units_per_em() as f32;
+        let mut buffer = rustybuzz::UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.set_direction(if rtl { rustybuzz::Direction::RightToLeft } else { rustybuzz::Direction::LeftToRight });
+        buffer.guess_segment_properties();
+        let output = rustybuzz::shape(face, &[], buffer);
+        output.glyph_infos().iter().zip(output.glyph_positions())
+            .map(|(info, position)| ShapedGlyph {
+                glyph: info.glyph_id as u16,
+                c: text[info.cluster as usize..].chars().next().unwrap_or(' '),
+                cluster: info.cluster as usize,
+                advance: position.x_advance as f32 * scale,
+                offset: vec2(position.x_offset as f32 * scale, position.y_offset as f32 * scale),
+            })
+            .collect()
+    }
+}
+
+// Splits spans into lines at '\n' and shapes each run of one bidi level and
+// span. Runs stay in logical order so layout_shaped can wrap them before
+// putting each wrapped line in visual order.
+pub fn shape_spans<F: ShapingFont>(fonts: &[F], spans: &[Span]) -> Vec<Vec<ShapedRun>> {
+    let mut text = String::new();
+    let mut starts = vec![];
+    for span in spans {
+        starts.push(text.len());
+        text += span.text;
+    }
+    let span_at = |index: usize| starts.partition_point(|start| *start <= index) - 1;
+
+    let bidi = BidiInfo::new(&text, None);
+    // each font is parsed once, by the first run that needs it
+    let mut faces: Vec<Option<rustybuzz::Face>> = fonts.iter().map(|_| None).collect();
+    let mut lines = vec![];
+    for paragraph in &bidi.paragraphs {
+        // '\n' separates paragraphs and stays at the end of them
+        let mut line = paragraph.range.clone();
+        if text[line.clone()].ends_with('\n') {
+            line.end -= 1;
+        }
+        // runs of one level and span
+        let mut runs: Vec<Range<usize>> = vec![];
+        let mut start = line.start;
+        for index in text[line.clone()].char_indices().map(|(index, _)| index + line.start) {
+            if bidi.levels[index] != bidi.levels[start] || span_at(index) != span_at(start) {
+                runs.push(start..index);
+                start = index;
+            }
+        }
+        if start < line.end {
+            runs.push(start..line.end);
+        }
+        let mut shaped = vec![];
+        for run in runs {
+            let level = bidi.levels[run.start];
+            let span_index = span_at(run.start);
+            let span = &spans[span_index];
+            let font = &fonts[span.font];
+            let offset = run.start - starts[span_index];
+            let face = faces[span.font].get_or_insert_with(|| font.shaper().face());
+            let glyphs = Shaper::shape_with(face, &text[run], font.font_size(), level.is_rtl()).into_iter()
+                .map(|glyph| ShapedGlyph { cluster: glyph.cluster + offset, ..glyph })
+                .collect();
+            shaped.push(ShapedRun {
+                span: span_index,
+                font: span.font,
+                color: span.color,
+                rtl: level.is_rtl(),
+                level: level.number(),
+                glyphs,
+            });
+        }
+        lines.push(shaped);
+    }
+    if text.is_empty() || text.ends_with('\n') {
+        lines.push(vec![]);
+    }
+    lines
+}
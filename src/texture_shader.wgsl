@@ -16,11 +16,17 @@ struct InstanceBuffer {
     @location(1) model_matrix_1: vec4<f32>,
     @location(2) model_matrix_2: vec4<f32>,
     @location(3) model_matrix_3: vec4<f32>, 
+    // offset and size of the part of the texture to show
+    @location(4) uv: vec4<f32>,
+    // repeats of the uv rect across the quad
+    @location(5) tiles: vec2<f32>,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
+    // 0..tiles across the quad, wrapped into the uv rect per fragment
     @location(0) tex_coords: vec2<f32>,
+    @location(1) uv: vec4<f32>,
 };
 
 @vertex
@@ -31,7 +37,8 @@ fn vs_main(@builtin(vertex_index) in_vertex_index: u32,
     let y = f32(vertices[in_vertex_index * 2 + 1]) - 0.5;
     var out: VertexOutput;
     out.clip_position = projection * mat * vec4<f32>(x, y, 0.0, 1.0);
-    out.tex_coords = vec2(x + 0.5, y + 0.5);
+    out.tex_coords = vec2(x + 0.5, y + 0.5) * buffer.tiles;
+    out.uv = buffer.uv;
     return out;
 }
 
@@ -40,6 +47,8 @@ fn vs_main(@builtin(vertex_index) in_vertex_index: u32,
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(diffuse_texture, diffuse_sampler, in.tex_coords);
+    // a single repeat ends exactly on 1, which fract would wrap to 0
+    let repeat = select(fract(in.tex_coords), in.tex_coords, in.tex_coords <= vec2(1.0));
+    return textureSample(diffuse_texture, diffuse_sampler, in.uv.xy + repeat * in.uv.zw);
 }
 
@@ -1,5 +1,6 @@
 use crate::{
-    mat::Mat4,
+    geom::{Obb, Rect},
+    mat::{vec2, vec4, Mat4, Vec2, Vec4},
     rrs::{self, Entry, EntryDiscriminants, Record, RenderConstruct, RenderRecordSystem, Settings, Update},
     texture::TextureInfo,
     util::indirect_handles::{Handle, HandleTracker, WeakHandle},
@@ -27,6 +28,109 @@ pub struct Renderer {
 pub struct RenderParams {
     pub matrix: Mat4,
     pub texture: WeakHandle<Texture>,
+    // the part of the texture to show, Rect::UNIT for all of it
+    pub uv: Rect,
+    // how many times uv repeats across the quad, (1, 1) to show it once
+    pub tiles: Vec2,
+}
+
+// Border widths in texels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Insets {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SliceMode {
+    Stretch,
+    // repeats whole texels, cutting off the last repeat
+    Tile,
+}
+
+// A nine-slice or nine-patch: `target` is split like the texture region by
+// `insets`, the corners keep their size and the edges and center stretch or
+// tile to fill the rest. Borders shrink evenly when the target is too
+// small to fit them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NineSlice {
+    // the region of the texture, in uvs
+    pub uv: Rect,
+    // the region's size in texels
+    pub size: Vec2,
+    pub insets: Insets,
+    pub target: Rect,
+    // world units per texel
+    pub scale: f32,
+    pub mode: SliceMode,
+}
+
+// One of the quads a NineSlice becomes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Piece {
+    pub target: Rect,
+    pub uv: Rect,
+    pub tiles: Vec2,
+}
+
+impl Insets {
+    pub fn uniform(width: f32) -> Insets {
+        Insets { left: width, top: width, right: width, bottom: width }
+    }
+
+    // From the stretchable middle of a `size` region, like the center of
+    // an atlas::SliceKey
+    pub fn from_center(size: Vec2, center: Rect) -> Insets {
+        Insets {
+            left: center.min.x,
+            top: center.min.y,
+            right: size.x - center.max.x,
+            bottom: size.y - center.max.y,
+        }
+    }
+}
+
+impl NineSlice {
+    // Left to right then top to bottom, leaving out empty ones
+    pub fn pieces(&self) -> Vec<Piece> {
+        let Insets { left, top, right, bottom } = self.insets;
+        let target_size = self.target.size();
+        // how much of the borders fit
+        let fit = |target: f32, start: f32, end: f32| {
+            let border = (start + end) * self.scale;
+            if border > target { target / border } else { 1.0 }
+        };
+        let (fit_x, fit_y) = (fit(target_size.x, left, right), fit(target_size.y, top, bottom));
+        let Rect { min, max } = self.target;
+        let xs = [min.x, min.x + left * self.scale * fit_x, max.x - right * self.scale * fit_x, max.x];
+        let ys = [min.y, min.y + top * self.scale * fit_y, max.y - bottom * self.scale * fit_y, max.y];
+        let us = [0.0, left, self.size.x - right, self.size.x];
+        let vs = [0.0, top, self.size.y - bottom, self.size.y];
+        let texel_uv = self.uv.size() / self.size;
+        let mut pieces = Vec::new();
+        for row in 0..3 {
+            for column in 0..3 {
+                let target = Rect::new(vec2(xs[column], ys[row]), vec2(xs[column + 1], ys[row + 1]));
+                let texels = Rect::new(vec2(us[column], vs[row]), vec2(us[column + 1], vs[row + 1]));
+                if target.width() <= 0.0 || target.height() <= 0.0 || texels.width() <= 0.0 || texels.height() <= 0.0 {
+                    continue;
+                }
+                // corners and the border side of edges always show once
+                let repeat = |length: f32, texels: f32, middle: bool| match self.mode {
+                    SliceMode::Tile if middle => length / (texels * self.scale),
+                    _ => 1.0,
+                };
+                let tiles = vec2(
+                    repeat(target.width(), texels.width(), column == 1),
+                    repeat(target.height(), texels.height(), row == 1));
+                let uv = Rect::new(self.uv.min + texels.min * texel_uv, self.uv.min + texels.max * texel_uv);
+                pieces.push(Piece { target, uv, tiles });
+            }
+        }
+        pieces
+    }
 }
 
 #[derive(Debug)]
@@ -39,9 +143,20 @@ struct TextureBindGroup {
     bind_group: wgpu::BindGroup,
 }
 
-#[repr(C, packed)]
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 struct InstanceBuffer {
     matrix: Mat4,
+    // uv offset and size
+    uv: Vec4,
+    tiles: Vec2,
+}
+
+impl RenderParams {
+    // the shader's unit square, in world space
+    pub fn bounds(&self) -> Rect {
+        Obb::from_matrix(&self.matrix).bounds()
+    }
 }
 
 impl InstanceBuffer {
@@ -53,6 +168,8 @@ impl InstanceBuffer {
             1 => Float32x4,
             2 => Float32x4,
             3 => Float32x4,
+            4 => Float32x4,
+            5 => Float32x2,
         ],
     };
 }
@@ -134,7 +251,10 @@ impl Construct {
                 },
                 primitive: Default::default(),
                 depth_stencil: None,
-                multisample: Default::default(),
+                multisample: wgpu::MultisampleState {
+                    count: rc.sample_count,
+                    ..Default::default()
+                },
                 fragment: Some(wgpu::FragmentState {
                     module: &shader,
                     entry_point: Some("fs_main"),
@@ -202,9 +322,9 @@ impl rrs::Renderer for Renderer {
         rc: &mut RenderContext,
         rpass: &mut wgpu::RenderPass,
         entry: &Entry,
-        _: &Settings,
+        settings: &Settings,
     ) {
-        let RenderParams { matrix, texture } = match entry {
+        let RenderParams { matrix, texture, uv, tiles } = match entry {
             Entry::Textured(p) => p,
             _ => panic!("Failed to call correct renderer!"),
         };
@@ -216,8 +336,13 @@ impl rrs::Renderer for Renderer {
         let offset: u64 = (size_of::<InstanceBuffer>() * (buf_index as usize))
             .try_into()
             .unwrap();
-        rc.queue
-            .write_buffer(&self.instance_buf, offset, matrix.as_ref());
+        let size = uv.size();
+        let instance = InstanceBuffer {
+            matrix: settings.snap_matrix(matrix),
+            uv: vec4(uv.min.x, uv.min.y, size.x, size.y),
+            tiles: *tiles,
+        };
+        rc.queue.write_buffer(&self.instance_buf, offset, bytemuck::bytes_of(&instance));
         rpass.set_pipeline(&self.pipeline);
         rpass.set_bind_group(0, Some(bind_group), &[]);
         rpass.set_vertex_buffer(0, self.instance_buf.slice(..));
@@ -264,6 +389,20 @@ impl rrs::Renderer for Renderer {
 }
 
 impl Construct {
+    // Draws `nine_slice` as up to nine textured entries
+    pub fn draw_nine_slice(
+        &mut self,
+        rc: &mut RenderContext,
+        record: &mut Record,
+        texture: WeakHandle<Texture>,
+        nine_slice: &NineSlice,
+    ) {
+        for Piece { target, uv, tiles } in nine_slice.pieces() {
+            let matrix = Mat4::box2d(target.center(), target.size());
+            self.draw(rc, record, RenderParams { matrix, texture, uv, tiles });
+        }
+    }
+
     pub fn init_texture<'a>(
         &mut self,
         rc: &mut RenderContext,
@@ -0,0 +1,79 @@
+use std::time::{Duration, Instant};
+
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+// Accumulates frame time and hands it out in fixed ticks. When a frame takes
+// longer than `max_catch_up` ticks the backlog is dropped instead of running
+// ever more updates to catch up.
+pub struct FixedTimestep {
+    tick: Duration,
+    max_catch_up: u32,
+    accumulator: Duration,
+    last: Option<Instant>,
+}
+
+impl FixedTimestep {
+    pub fn new(tick_rate: f64, max_catch_up: u32) -> FixedTimestep {
+        assert!(tick_rate > 0.0, "Tick rate must be positive");
+        FixedTimestep {
+            tick: Duration::from_secs_f64(1.0 / tick_rate),
+            max_catch_up: max_catch_up.max(1),
+            accumulator: Duration::ZERO,
+            last: None,
+        }
+    }
+
+    pub fn tick(&self) -> Duration {
+        self.tick
+    }
+
+    pub fn dt(&self) -> f32 {
+        self.tick.as_secs_f32()
+    }
+
+    // How many updates to run for a frame that took `elapsed`
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += elapsed;
+        let mut ticks = 0;
+        while self.accumulator >= self.tick {
+            if ticks == self.max_catch_up {
+                self.accumulator = Duration::from_nanos(
+                    (self.accumulator.as_nanos() % self.tick.as_nanos()) as u64);
+                break;
+            }
+            self.accumulator -= self.tick;
+            ticks += 1;
+        }
+        ticks
+    }
+
+    // Samples the clock and advances by the time since the previous call.
+    // The first call only starts the clock.
+    pub fn step<C: Clock>(&mut self, clock: &C) -> u32 {
+        let now = clock.now();
+        let elapsed = self.last.map_or(Duration::ZERO, |last| now.saturating_duration_since(last));
+        self.last = Some(now);
+        self.advance(elapsed)
+    }
+
+    // How long until the accumulator holds another whole tick
+    pub fn until_next_tick(&self) -> Duration {
+        self.tick.saturating_sub(self.accumulator)
+    }
+
+    // Fraction of a tick left in the accumulator, for interpolating between
+    // the previous and current update state while drawing
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.tick.as_secs_f32()
+    }
+}
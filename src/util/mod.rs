@@ -1 +1,103 @@
 pub mod indirect_handles;
+
+pub trait WordSpaceIterable<'a> {
+    fn words_spaces(self) -> WordSpaceIterator<'a>;
+}
+impl<'a> WordSpaceIterable<'a> for std::str::Chars<'a> {
+    fn words_spaces(self) -> WordSpaceIterator<'a> {
+        WordSpaceIterator {
+            chars: self,
+            current: None,
+        }
+    }
+}
+
+// Splits text into alternating runs of words and whitespace
+pub struct WordSpaceIterator<'a> {
+    chars: std::str::Chars<'a>,
+    current: Option<char>
+}
+
+pub fn is_whitespace(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\n'
+}
+
+impl Iterator for WordSpaceIterator<'_> {
+    type Item = String;
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = {
+            if let Some(current) = self.current {
+                current
+            } else if let Some(next) = self.chars.next() {
+                next
+            } else {
+                self.current = None;
+                return None
+            }
+        };
+        self.current = None;
+        let whitespace = is_whitespace(start);
+        let mut word = start.to_string();
+        for c in &mut self.chars {
+            if is_whitespace(c) == whitespace {
+                word.push(c);
+            } else {
+                // preserve the unused character (reason why we can't use take_while)
+                self.current = Some(c);
+                break;
+            }
+        }
+
+        Some(word)
+    }
+}
+
+pub fn clampi(val: i32, start: i32, end: i32) -> i32 {
+    if end < start {
+        return start
+    }
+    val.clamp(start, end)
+}
+
+// Versions of an asset made for different scale factors, like a font
+// rasterized at 16 and 32 pixels or a texture and its @2x version. Each is
+// added with the scale factor it's made for, where 1 is the normal size.
+pub struct ScaleVariants<T> {
+    // sorted by scale
+    variants: Vec<(f64, T)>,
+}
+
+impl<T> Default for ScaleVariants<T> {
+    fn default() -> Self {
+        ScaleVariants { variants: Vec::new() }
+    }
+}
+
+impl<T> ScaleVariants<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, scale: f64, variant: T) {
+        let index = self.variants.partition_point(|(other, _)| *other <= scale);
+        self.variants.insert(index, (scale, variant));
+    }
+
+    // The smallest variant made for at least `scale_factor`, so it's only
+    // ever shrunk, or the largest one if none are. Returns the variant's
+    // scale too, drawing at 1 / scale keeps it the size of the normal one.
+    pub fn pick(&self, scale_factor: f64) -> Option<(f64, &T)> {
+        self.variants.iter()
+            .find(|(scale, _)| *scale >= scale_factor)
+            .or(self.variants.last())
+            .map(|(scale, variant)| (*scale, variant))
+    }
+
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+}
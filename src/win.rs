@@ -1,11 +1,18 @@
-use std::{sync::Arc, time::Instant};
+use std::{sync::Arc, time::{Duration, Instant}};
 
 use pollster::FutureExt;
 use wgpu::{DeviceDescriptor, InstanceDescriptor, PowerPreference, RequestAdapterOptions, SurfaceConfiguration, TextureUsages};
-use winit::{application::ApplicationHandler, event::WindowEvent, event_loop::{ControlFlow, EventLoop}, window::Window};
+use winit::{application::ApplicationHandler, dpi::{LogicalSize, PhysicalSize}, event::WindowEvent, event_loop::{ControlFlow, EventLoop}, window::{Fullscreen, Window}};
+
+use crate::{input::InputState, timestep::{FixedTimestep, SystemClock}};
 
 pub trait Client {
-    fn draw(&mut self, render_context: &mut RenderContext);
+    // `alpha` is how far between the last two updates this frame is drawn
+    fn draw(&mut self, render_context: &mut RenderContext, view: &wgpu::TextureView, alpha: f32);
+    // Called at the fixed tick rate from WindowConfig, `dt` is the tick length
+    fn update(&mut self, _render_context: &mut RenderContext, _dt: f32) {}
+    // Also called when the scale factor changes, with the same size if the
+    // window didn't change physically
     fn resize(&mut self, render_context: &mut RenderContext, _size: winit::dpi::PhysicalSize<u32>);
     fn handle_event(&mut self, render_context: &mut RenderContext, event: &winit::event::WindowEvent) -> EventState;
 }
@@ -24,10 +31,15 @@ impl EventState {
 }
 
 pub struct App<C: Client, F: FnOnce(&mut RenderContext) -> C> {
+    config: WindowConfig,
     window: Option<Arc<Window>>,
     render_context: Option<RenderContext>,
     client: Option<C>,
     init_func: Option<F>,
+    timestep: FixedTimestep,
+    last_frame: Option<Instant>,
+    minimized: bool,
+    occluded: bool,
 }
 
 pub struct RenderContext {
@@ -37,8 +49,19 @@ pub struct RenderContext {
     pub queue: wgpu::Queue,
     pub surface_format: wgpu::TextureFormat,
     pub surface: wgpu::Surface<'static>,
+    pub surface_config: SurfaceConfiguration,
+    // sample count every renderer pipeline is built with, 1 without MSAA
+    pub sample_count: u32,
+    // multisampled color texture resolved into the surface when sample_count > 1
+    pub msaa_view: Option<wgpu::TextureView>,
+    // WindowConfig::required_features the adapter doesn't have, the device
+    // is created without them
+    pub missing_features: wgpu::Features,
     pub window: Arc<Window>,
+    input: InputState,
     request_to_close: bool,
+    redraw_requested: bool,
+    simulated_loss: bool,
     start: Instant,
     last: Instant,
     current: Instant,
@@ -54,12 +77,206 @@ impl RenderContext {
     pub fn delta_time(&self) -> f32 {
         self.current.duration_since(self.last).as_secs_f32()
     }
+    pub fn input(&self) -> &InputState {
+        &self.input
+    }
+    // Physical pixels per logical pixel, see Mat4::ortho_logical
+    pub fn scale_factor(&self) -> f64 {
+        self.input.scale_factor()
+    }
+    pub fn physical_size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.surface_config.width, self.surface_config.height)
+    }
+    pub fn logical_size(&self) -> LogicalSize<f32> {
+        self.physical_size().to_logical(self.scale_factor())
+    }
+    // Marks the frame dirty, needed for anything to be drawn with RedrawMode::OnDemand
+    pub fn request_redraw(&mut self) {
+        self.redraw_requested = true;
+    }
+    // Makes the next surface acquisition fail with SurfaceError::Lost
+    pub fn simulate_surface_loss(&mut self) {
+        self.simulated_loss = true;
+    }
+}
+
+pub enum Acquired<T> {
+    Frame(T),
+    Skip,
+    Exit,
+}
+
+pub trait Swapchain {
+    type Frame;
+    fn acquire(&mut self) -> Result<Self::Frame, wgpu::SurfaceError>;
+    fn reconfigure(&mut self);
+}
+
+// Lost and Outdated surfaces are reconfigured and acquired once more, timeouts
+// skip the frame and running out of memory exits the app.
+pub fn acquire_frame<S: Swapchain>(swapchain: &mut S) -> Acquired<S::Frame> {
+    let retry = match swapchain.acquire() {
+        Ok(frame) => return Acquired::Frame(frame),
+        Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+            swapchain.reconfigure();
+            swapchain.acquire()
+        },
+        Err(err) => Err(err),
+    };
+    match retry {
+        Ok(frame) => Acquired::Frame(frame),
+        Err(wgpu::SurfaceError::OutOfMemory) => Acquired::Exit,
+        Err(_) => Acquired::Skip,
+    }
+}
+
+impl Swapchain for RenderContext {
+    type Frame = wgpu::SurfaceTexture;
+
+    fn acquire(&mut self) -> Result<wgpu::SurfaceTexture, wgpu::SurfaceError> {
+        if self.simulated_loss {
+            self.simulated_loss = false;
+            return Err(wgpu::SurfaceError::Lost);
+        }
+        self.surface.get_current_texture()
+    }
+
+    fn reconfigure(&mut self) {
+        if self.surface_config.width == 0 || self.surface_config.height == 0 {
+            return;
+        }
+        self.surface.configure(&self.device, &self.surface_config);
+        self.msaa_view = (self.sample_count > 1).then(|| {
+            multisampled_texture(self, self.surface_config.width, self.surface_config.height)
+                .create_view(&Default::default())
+        });
+    }
+}
+
+pub fn multisampled_texture(rc: &RenderContext, width: u32, height: u32) -> wgpu::Texture {
+    rc.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Multisampled color"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: rc.sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format: rc.surface_format,
+        usage: TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    })
+}
+
+pub struct WindowConfig {
+    pub title: String,
+    // None leaves the initial size up to the platform
+    pub size: Option<winit::dpi::Size>,
+    pub resizable: bool,
+    // borderless fullscreen on the current monitor
+    pub fullscreen: bool,
+    // tried in order, Fifo is used when none are supported
+    pub present_modes: Vec<wgpu::PresentMode>,
+    // lowered to the highest count the surface format supports
+    pub sample_count: u32,
+    pub backends: wgpu::Backends,
+    pub power_preference: PowerPreference,
+    // features the adapter lacks are dropped
+    pub required_features: wgpu::Features,
+    // Client::update calls per second
+    pub tick_rate: f64,
+    // most updates run for a single frame before the backlog is dropped
+    pub max_catch_up: u32,
+    // frames per second to cap drawing at, None leaves it to the present mode
+    pub target_fps: Option<f64>,
+    pub redraw_mode: RedrawMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedrawMode {
+    // draw every frame
+    Continuous,
+    // only draw after RenderContext::request_redraw, a resize or an expose
+    OnDemand,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: "Test".to_string(),
+            size: None,
+            resizable: true,
+            fullscreen: false,
+            present_modes: vec![wgpu::PresentMode::Mailbox, wgpu::PresentMode::Fifo],
+            sample_count: 1,
+            backends: wgpu::Backends::PRIMARY,
+            power_preference: PowerPreference::HighPerformance,
+            required_features: wgpu::Features::empty(),
+            tick_rate: 60.0,
+            max_catch_up: 5,
+            target_fps: None,
+            redraw_mode: RedrawMode::Continuous,
+        }
+    }
+}
+
+impl WindowConfig {
+    pub fn vsync(vsync: bool) -> Self {
+        let present_modes = if vsync {
+            vec![wgpu::PresentMode::Fifo]
+        } else {
+            vec![wgpu::PresentMode::Immediate, wgpu::PresentMode::Mailbox, wgpu::PresentMode::Fifo]
+        };
+        Self {
+            present_modes,
+            ..Default::default()
+        }
+    }
+}
+
+pub fn pick_present_mode(requested: &[wgpu::PresentMode], available: &[wgpu::PresentMode]) -> wgpu::PresentMode {
+    requested.iter()
+        .find(|mode| available.contains(mode))
+        .copied()
+        .unwrap_or(wgpu::PresentMode::Fifo)
+}
+
+// Counts other than 1 and 4 need TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES on
+// the device, so they're only tried when `adapter_specific` says it will be
+// requested
+pub fn pick_sample_count(requested: u32, adapter_specific: bool, supported: impl Fn(u32) -> bool) -> u32 {
+    let candidates: &[u32] = if adapter_specific { &[16, 8, 4, 2] } else { &[4] };
+    candidates.iter()
+        .copied()
+        .find(|count| *count <= requested && supported(*count))
+        .unwrap_or(1)
+}
+
+fn request_adapter(config: &WindowConfig, window: &Arc<Window>) -> Option<(wgpu::Instance, wgpu::Surface<'static>, wgpu::Adapter)> {
+    let instance = wgpu::Instance::new(&InstanceDescriptor {
+        backends: config.backends,
+        ..Default::default()
+    });
+    let surface: wgpu::Surface<'static> = instance.create_surface(window.clone()).ok()?;
+    let adapter = instance.request_adapter(&RequestAdapterOptions {
+        power_preference: config.power_preference,
+        force_fallback_adapter: false,
+        compatible_surface: Some(&surface),
+    }).block_on().ok()?;
+    Some((instance, surface, adapter))
 }
 
-pub fn run<C: Client, F: FnOnce(&mut RenderContext) -> C>(init_func: F) {
+pub fn run<C: Client, F: FnOnce(&mut RenderContext) -> C>(config: WindowConfig, init_func: F) {
     let event_loop = EventLoop::new().unwrap();
-    event_loop.set_control_flow(ControlFlow::Poll);
+    let timestep = FixedTimestep::new(config.tick_rate, config.max_catch_up);
     event_loop.run_app(&mut App::<C, F> {
+        config,
+        timestep,
+        last_frame: None,
+        minimized: false,
+        occluded: false,
         window: None,
         render_context: None,
         client: None,
@@ -69,33 +286,57 @@ pub fn run<C: Client, F: FnOnce(&mut RenderContext) -> C>(init_func: F) {
 
 impl<C: Client, F: FnOnce(&mut RenderContext) -> C> ApplicationHandler for App<C, F> {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
-        let attributes = Window::default_attributes()
-            .with_title("Test");
+        let config = &self.config;
+        let mut attributes = Window::default_attributes()
+            .with_title(config.title.clone())
+            .with_resizable(config.resizable);
+        if let Some(size) = config.size {
+            attributes = attributes.with_inner_size(size);
+        }
+        if config.fullscreen {
+            attributes = attributes.with_fullscreen(Some(Fullscreen::Borderless(None)));
+        }
         self.window = Some(Arc::new(event_loop.create_window(attributes).unwrap()));
         let window = self.window.as_mut().unwrap().clone();
-        let instance = wgpu::Instance::new(&InstanceDescriptor {
-            backends: wgpu::Backends::PRIMARY,
-            ..Default::default()
-        });
-        let surface: wgpu::Surface<'static> = instance.create_surface(window.clone()).unwrap();
-        let adapter = instance.request_adapter(&RequestAdapterOptions {
-            power_preference: PowerPreference::HighPerformance,
-            force_fallback_adapter: false,
-            compatible_surface: Some(&surface),
-        }).block_on().unwrap();
-        let (device, queue) = adapter.request_device(&DeviceDescriptor {
-            label: Some("Test device"),
-            required_features: wgpu::Features::empty(),
-            required_limits: wgpu::Limits::default(),
-            memory_hints: wgpu::MemoryHints::default(),
-            trace: wgpu::Trace::Off,
-        }).block_on().unwrap();
+        let (instance, surface, adapter) = request_adapter(config, &window)
+            .or_else(|| request_adapter(&WindowConfig {
+                backends: wgpu::Backends::all(),
+                power_preference: PowerPreference::default(),
+                ..WindowConfig::default()
+            }, &window))
+            .expect("No graphics adapter available");
         let surface_capabilities = surface.get_capabilities(&adapter);
         let surface_format = surface_capabilities.formats.iter()
             .find(|f| f.is_srgb())
             .copied()
             .unwrap_or(surface_capabilities.formats[0]);
+        let format_features = adapter.get_texture_format_features(surface_format);
+        let adapter_specific = adapter.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+        let sample_count = pick_sample_count(config.sample_count, adapter_specific, |count| format_features.flags.sample_count_supported(count));
+        let mut required_features = config.required_features & adapter.features();
+        let missing_features = config.required_features - required_features;
+        if sample_count != 1 && sample_count != 4 {
+            required_features |= wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+        }
+        let (device, queue) = adapter.request_device(&DeviceDescriptor {
+            label: Some("Test device"),
+            required_features,
+            required_limits: wgpu::Limits::default().using_resolution(adapter.limits()),
+            memory_hints: wgpu::MemoryHints::default(),
+            trace: wgpu::Trace::Off,
+        }).block_on().unwrap();
+        let present_mode = pick_present_mode(&config.present_modes, &surface_capabilities.present_modes);
         let size = window.inner_size();
+        let surface_config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format: surface_format,
+            width: size.width,
+            height: size.height,
+            present_mode,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![surface_format],
+        };
         let start = Instant::now();
         self.render_context = Some(RenderContext {
             instance,
@@ -104,8 +345,15 @@ impl<C: Client, F: FnOnce(&mut RenderContext) -> C> ApplicationHandler for App<C
             queue,
             surface_format,
             surface,
+            surface_config,
+            sample_count,
+            msaa_view: None,
+            missing_features,
+            input: InputState::new(window.scale_factor()),
             window,
             request_to_close: false,
+            redraw_requested: true,
+            simulated_loss: false,
             start,
             last: start,
             current: start,
@@ -126,12 +374,36 @@ impl<C: Client, F: FnOnce(&mut RenderContext) -> C> ApplicationHandler for App<C
             Some(window) => window,
             None => return,
         };
+        if let Some(rc) = self.render_context.as_mut() {
+            rc.input.handle_event(&event);
+        }
         match event {
             WindowEvent::RedrawRequested => {
-                self.draw();
-                self.window.as_mut().unwrap().request_redraw();
+                self.draw(event_loop);
+                if let Some(rc) = self.render_context.as_mut() {
+                    rc.input.end_frame();
+                }
             },
             WindowEvent::Resized(size) => self.configure_window(size),
+            WindowEvent::ScaleFactorChanged { .. } => {
+                // a Resized follows if the physical size changes too, but
+                // logical sizes change either way
+                if let Some(rc) = self.render_context.as_mut() {
+                    let size = rc.physical_size();
+                    rc.request_redraw();
+                    if !self.minimized {
+                        self.resize(size);
+                    }
+                }
+                let _ = self.handle_event(&event);
+            },
+            WindowEvent::Occluded(occluded) => {
+                self.occluded = occluded;
+                if !occluded {
+                    self.render_context.as_mut().map(RenderContext::request_redraw);
+                }
+                let _ = self.handle_event(&event);
+            },
             WindowEvent::CloseRequested => {
                 self.handle_event(&event)
                     .is_skipped()
@@ -146,6 +418,32 @@ impl<C: Client, F: FnOnce(&mut RenderContext) -> C> ApplicationHandler for App<C
                  .then(|| event_loop.exit()));
     }
 
+    fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        // updates keep their rate even when nothing is being drawn
+        self.update();
+        let (Some(window), Some(rc)) = (self.window.as_ref(), self.render_context.as_ref()) else { return };
+        let now = Instant::now();
+        let next_tick = now + self.timestep.until_next_tick();
+        let idle = self.minimized || self.occluded
+            || (self.config.redraw_mode == RedrawMode::OnDemand && !rc.redraw_requested);
+        if idle {
+            event_loop.set_control_flow(ControlFlow::WaitUntil(next_tick));
+            return;
+        }
+        let next_frame = self.config.target_fps
+            .zip(self.last_frame)
+            .map(|(fps, last_frame)| last_frame + Duration::from_secs_f64(1.0 / fps));
+        match next_frame {
+            Some(next_frame) if next_frame > now => {
+                event_loop.set_control_flow(ControlFlow::WaitUntil(next_frame.min(next_tick)));
+            },
+            _ => {
+                event_loop.set_control_flow(ControlFlow::Wait);
+                window.request_redraw();
+            },
+        }
+    }
+
     fn exiting(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         let _ = event_loop;
     }
@@ -161,27 +459,55 @@ impl<C: Client, F: FnOnce(&mut RenderContext) -> C> App<C, F> {
     }
 
     fn configure_window(&mut self, size: winit::dpi::PhysicalSize<u32>) {
+        // minimized windows report a zero size, keep the old configuration until restored
+        self.minimized = size.width == 0 || size.height == 0;
+        if self.minimized {
+            return;
+        }
         let render_state = self.render_context.as_mut().unwrap();
-        render_state.surface.configure(&render_state.device, &SurfaceConfiguration {
-            usage: TextureUsages::RENDER_ATTACHMENT,
-            format: render_state.surface_format,
-            width: size.width,
-            height: size.height,
-            present_mode: wgpu::PresentMode::Mailbox,
-            desired_maximum_frame_latency: 2,
-            alpha_mode: wgpu::CompositeAlphaMode::Auto,
-            view_formats: vec![render_state.surface_format],
-        });
+        render_state.redraw_requested = true;
+        render_state.surface_config.width = size.width;
+        render_state.surface_config.height = size.height;
+        render_state.reconfigure();
         self.resize(size);
     }
 
-    fn draw(&mut self) {
-        self.render_context.as_mut().map(|rc: &mut RenderContext| {
-            rc.last = rc.current;
-            rc.current = Instant::now();
-            self.client.as_mut().unwrap()
-                .draw(rc);
-        });
+    // Runs the fixed updates that are due, whether or not a frame is drawn
+    fn update(&mut self) {
+        let (Some(rc), Some(client)) = (self.render_context.as_mut(), self.client.as_mut()) else { return };
+        for _ in 0..self.timestep.step(&SystemClock) {
+            client.update(rc, self.timestep.dt());
+        }
+    }
+
+    fn draw(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        // nothing would be seen, and some platforms block presenting
+        if self.minimized || self.occluded {
+            return;
+        }
+        // catch up to the moment the frame is drawn
+        self.update();
+        let Some(rc) = self.render_context.as_mut() else { return };
+        let client = self.client.as_mut().unwrap();
+        let output = match acquire_frame(rc) {
+            Acquired::Frame(output) => output,
+            Acquired::Skip => return,
+            Acquired::Exit => {
+                event_loop.exit();
+                return;
+            },
+        };
+        rc.last = rc.current;
+        rc.current = Instant::now();
+        rc.redraw_requested = false;
+        self.last_frame = Some(rc.current);
+        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        client.draw(rc, &view, self.timestep.alpha());
+        let suboptimal = output.suboptimal;
+        output.present();
+        if suboptimal {
+            rc.reconfigure();
+        }
     }
     fn resize(&mut self, size: winit::dpi::PhysicalSize<u32>) {
         self.client.as_mut().unwrap()
@@ -0,0 +1,132 @@
+use winit::{
+    event::{DeviceId, ElementState, MouseButton, WindowEvent},
+    keyboard::{Key, NamedKey},
+};
+
+use graphics2d::action::{ActionMap, AxisBinding, Bindings, Button, GamepadAxis, GamepadButton};
+
+fn bindings() -> Bindings {
+    let mut bindings = Bindings::new();
+    bindings.bind("jump", Button::Key(Key::Named(NamedKey::Space)));
+    bindings.bind("jump", Button::Gamepad(GamepadButton::South));
+    bindings.bind("fire", Button::Mouse(MouseButton::Left));
+    bindings.bind_axis("move_x", AxisBinding::Buttons {
+        negative: Button::Key(Key::Character("a".into())),
+        positive: Button::Key(Key::Character("d".into())),
+    });
+    bindings.bind_axis("move_x", AxisBinding::Gamepad {
+        axis: GamepadAxis::LeftStickX,
+        dead_zone: 0.2,
+        invert: false,
+    });
+    bindings
+}
+
+fn mouse(button: MouseButton, state: ElementState) -> WindowEvent {
+    WindowEvent::MouseInput { device_id: DeviceId::dummy(), state, button }
+}
+
+#[test]
+fn button_edges_last_one_frame() {
+    let mut actions = ActionMap::new(bindings());
+    let space = Key::Named(NamedKey::Space);
+    actions.key(&space, ElementState::Pressed, false);
+    assert!(actions.pressed("jump"));
+    assert!(actions.down("jump"));
+    actions.end_frame();
+    actions.key(&space, ElementState::Pressed, true);
+    assert!(!actions.pressed("jump"));
+    assert!(actions.down("jump"));
+    actions.key(&space, ElementState::Released, false);
+    assert!(actions.released("jump"));
+    assert!(!actions.down("jump"));
+}
+
+#[test]
+fn any_bound_button_triggers_the_action() {
+    let mut actions = ActionMap::new(bindings());
+    actions.gamepad_button(GamepadButton::South, ElementState::Pressed);
+    assert!(actions.pressed("jump"));
+    assert!(!actions.pressed("fire"));
+}
+
+#[test]
+fn mouse_events_are_consumed_when_bound() {
+    let mut actions = ActionMap::new(bindings());
+    assert!(!actions.handle_event(&mouse(MouseButton::Left, ElementState::Pressed)).is_skipped());
+    assert!(actions.down("fire"));
+    assert!(actions.handle_event(&mouse(MouseButton::Right, ElementState::Pressed)).is_skipped());
+}
+
+#[test]
+fn losing_focus_releases_everything() {
+    let mut actions = ActionMap::new(bindings());
+    actions.handle_event(&mouse(MouseButton::Left, ElementState::Pressed));
+    actions.handle_event(&WindowEvent::Focused(false));
+    assert!(!actions.down("fire"));
+    assert!(actions.released("fire"));
+}
+
+#[test]
+fn key_axis_ignores_case() {
+    let mut actions = ActionMap::new(bindings());
+    actions.key(&Key::Character("D".into()), ElementState::Pressed, false);
+    assert_eq!(actions.axis("move_x"), 1.0);
+    actions.key(&Key::Character("a".into()), ElementState::Pressed, false);
+    assert_eq!(actions.axis("move_x"), 0.0);
+    actions.key(&Key::Character("d".into()), ElementState::Released, false);
+    assert_eq!(actions.axis("move_x"), -1.0);
+}
+
+#[test]
+fn gamepad_axis_dead_zone() {
+    let mut actions = ActionMap::new(bindings());
+    actions.gamepad_axis(GamepadAxis::LeftStickX, 0.1);
+    assert_eq!(actions.axis("move_x"), 0.0);
+    actions.gamepad_axis(GamepadAxis::LeftStickX, -0.5);
+    assert_eq!(actions.axis("move_x"), -0.5);
+    assert_eq!(actions.axis("unbound"), 0.0);
+}
+
+#[test]
+fn bindings_round_trip_through_json() {
+    let bindings = bindings();
+    let loaded = Bindings::from_json(&bindings.to_json()).unwrap();
+    assert_eq!(loaded, bindings);
+    assert!(Bindings::from_json("{").is_err());
+}
+
+#[test]
+fn rebinding_replaces_the_old_keys() {
+    let mut actions = ActionMap::new(bindings());
+    actions.bindings.unbind("jump");
+    actions.bindings.bind("jump", Button::Key(Key::Character("W".into())));
+    assert!(!actions.key(&Key::Named(NamedKey::Space), ElementState::Pressed, false));
+    actions.key(&Key::Character("w".into()), ElementState::Pressed, false);
+    assert!(actions.pressed("jump"));
+}
+
+#[test]
+fn rebinding_releases_held_buttons() {
+    let mut actions = ActionMap::new(bindings());
+    let space = Key::Named(NamedKey::Space);
+    actions.key(&space, ElementState::Pressed, false);
+    actions.rebind("jump", vec![Button::Key(Key::Character("w".into()))]);
+    assert!(!actions.down("jump") && !actions.pressed("jump"));
+    // space goes to another action while still held from before
+    actions.rebind("fire", vec![Button::Key(space.clone())]);
+    assert!(!actions.down("fire") && !actions.pressed("fire"));
+    actions.key(&space, ElementState::Pressed, false);
+    assert!(actions.pressed("fire"));
+
+    // held buttons that stay bound keep their state
+    let mut actions = ActionMap::new(bindings());
+    actions.handle_event(&mouse(MouseButton::Left, ElementState::Pressed));
+    let mut replaced = bindings();
+    replaced.unbind("jump");
+    actions.set_bindings(replaced);
+    assert!(actions.down("fire"));
+    actions.set_bindings(Bindings::new());
+    actions.set_bindings(bindings());
+    assert!(!actions.down("fire"));
+}
@@ -0,0 +1,168 @@
+use graphics2d::animation::{Animations, Clip, Playback, Player, SpriteSheet};
+use graphics2d::geom::Rect;
+use graphics2d::mat::vec2;
+use winit::dpi::PhysicalSize;
+
+fn size(width: u32, height: u32) -> PhysicalSize<u32> {
+    PhysicalSize::new(width, height)
+}
+
+// four 16x16 frames in a row
+fn animations(playback: Playback) -> Animations {
+    let mut animations = Animations::new(SpriteSheet::grid(size(64, 16), size(16, 16)));
+    animations.add("walk", Clip::new(playback).frames(0..4, 0.25).event(2, "step"));
+    animations.add("idle", Clip::new(Playback::Loop).frame(3, 1.0));
+    animations
+}
+
+// the sheet frame after each of `steps` updates of `dt`
+fn frames(player: &mut Player, animations: &Animations, dt: f32, steps: usize) -> Vec<usize> {
+    (0..steps)
+        .map(|_| {
+            player.update(animations, dt);
+            player.frame(animations).unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn grids_slice_rows_then_columns() {
+    let sheet = SpriteSheet::grid(size(48, 32), size(16, 16));
+    assert_eq!(sheet.len(), 6);
+    assert_eq!(sheet.frames[1], Rect::new(vec2(16.0, 0.0), vec2(32.0, 16.0)));
+    assert_eq!(sheet.frames[3], Rect::new(vec2(0.0, 16.0), vec2(16.0, 32.0)));
+    // partial cells are dropped
+    assert_eq!(SpriteSheet::grid(size(50, 20), size(16, 16)).len(), 3);
+
+    let spaced = SpriteSheet::grid_spaced(size(38, 20), size(16, 16), 2, 2);
+    assert_eq!(spaced.len(), 2);
+    assert_eq!(spaced.frames[1], Rect::new(vec2(20.0, 2.0), vec2(36.0, 18.0)));
+}
+
+#[test]
+fn frames_map_to_uvs() {
+    let sheet = SpriteSheet::grid(size(64, 32), size(16, 16));
+    assert_eq!(sheet.uv(0), Some(Rect::new(vec2(0.0, 0.0), vec2(0.25, 0.5))));
+    assert_eq!(sheet.uv(5), Some(Rect::new(vec2(0.25, 0.5), vec2(0.5, 1.0))));
+    assert_eq!(sheet.uv(8), None);
+}
+
+#[test]
+fn loops_wrap_around() {
+    let animations = animations(Playback::Loop);
+    let mut player = Player::new();
+    assert_eq!(player.frame(&animations), None);
+    player.play("walk");
+    assert_eq!(player.frame(&animations), Some(0));
+    assert_eq!(frames(&mut player, &animations, 0.125, 10), [0, 1, 1, 2, 2, 3, 3, 0, 0, 1]);
+    assert!(!player.is_finished());
+    // long frames skip several
+    assert_eq!(frames(&mut player, &animations, 0.75, 2), [0, 3]);
+}
+
+#[test]
+fn ping_pong_turns_at_the_ends() {
+    let animations = animations(Playback::PingPong);
+    let mut player = Player::new();
+    player.play("walk");
+    assert_eq!(frames(&mut player, &animations, 0.25, 8), [1, 2, 3, 2, 1, 0, 1, 2]);
+
+    let mut animations = animations;
+    animations.add("single", Clip::new(Playback::PingPong).frame(1, 0.25));
+    player.play("single");
+    assert_eq!(frames(&mut player, &animations, 0.25, 3), [1, 1, 1]);
+}
+
+#[test]
+fn once_stops_on_the_last_frame() {
+    let animations = animations(Playback::Once);
+    let mut player = Player::new();
+    player.play("walk");
+    assert_eq!(frames(&mut player, &animations, 0.25, 3), [1, 2, 3]);
+    assert!(!player.is_finished());
+    assert_eq!(frames(&mut player, &animations, 0.25, 2), [3, 3]);
+    assert!(player.is_finished());
+    // playing it again carries on, restarting doesn't
+    player.play("walk");
+    assert!(player.is_finished());
+    player.restart("walk");
+    assert_eq!(player.frame(&animations), Some(0));
+    assert!(!player.is_finished());
+}
+
+#[test]
+fn events_fire_when_frames_are_entered() {
+    let mut animations = animations(Playback::Loop);
+    animations.add("attack", Clip::new(Playback::Once).frames([0, 1], 0.5).event(0, "swing").event(1, "hit"));
+    let mut player = Player::new();
+    player.play("walk");
+    assert!(player.update(&animations, 0.25).is_empty());
+    assert_eq!(player.update(&animations, 0.25), ["step"]);
+    assert!(player.update(&animations, 0.25).is_empty());
+    // a whole loop in one update still fires once per entry
+    assert_eq!(player.update(&animations, 1.0), ["step"]);
+    // more loops than that don't fire again
+    assert_eq!(player.update(&animations, 2.0), ["step"]);
+
+    // the first frame's events come with the first update
+    player.play("attack");
+    assert_eq!(player.update(&animations, 0.0), ["swing"]);
+    assert_eq!(player.update(&animations, 1.0), ["hit"]);
+    assert!(player.update(&animations, 1.0).is_empty());
+}
+
+#[test]
+fn switching_clips_and_speed() {
+    let animations = animations(Playback::Loop);
+    let mut player = Player::new();
+    player.play("walk");
+    player.update(&animations, 0.5);
+    assert_eq!(player.frame(&animations), Some(2));
+    // the same clip carries on
+    player.play("walk");
+    assert_eq!(player.position(), 2);
+    player.play("idle");
+    assert_eq!((player.clip(), player.frame(&animations)), (Some("idle"), Some(3)));
+
+    player.speed = 2.0;
+    player.play("walk");
+    assert_eq!(frames(&mut player, &animations, 0.125, 3), [1, 2, 3]);
+    assert_eq!(player.uv(&animations), Some(Rect::new(vec2(0.75, 0.0), vec2(1.0, 1.0))));
+}
+
+#[test]
+fn playback_is_deterministic() {
+    let animations = animations(Playback::PingPong);
+    let run = |dts: &[f32]| {
+        let mut player = Player::new();
+        player.play("walk");
+        dts.iter().map(|dt| (player.update(&animations, *dt).len(), player.frame(&animations))).collect::<Vec<_>>()
+    };
+    let dts: Vec<f32> = (0..200).map(|i| (i % 7) as f32 / 60.0).collect();
+    assert_eq!(run(&dts), run(&dts));
+}
+
+#[test]
+fn huge_steps_skip_whole_loops() {
+    let animations = animations(Playback::Loop);
+    let mut player = Player::new();
+    player.play("walk");
+    assert_eq!(player.update(&animations, 1000.5), ["step"]);
+    assert_eq!(player.position(), 2);
+}
+
+#[test]
+#[should_panic]
+fn frames_must_last() {
+    let mut animations = animations(Playback::Loop);
+    let mut clip = Clip::new(Playback::Loop).frame(0, 0.25);
+    clip.frames[0].duration = 0.0;
+    animations.add("broken", clip);
+}
+
+#[test]
+#[should_panic]
+fn clips_must_stay_in_the_sheet() {
+    let mut animations = animations(Playback::Loop);
+    animations.add("broken", Clip::new(Playback::Loop).frame(4, 0.25));
+}
@@ -0,0 +1,148 @@
+use graphics2d::animation::{Playback, Player};
+use graphics2d::atlas::{Atlas, AtlasError};
+use graphics2d::geom::{Obb, Rect};
+use graphics2d::mat::vec2;
+use winit::dpi::PhysicalSize;
+
+mod common;
+use common::assert_within;
+
+fn aseprite() -> Atlas {
+    Atlas::from_aseprite(include_str!("data/aseprite.json")).unwrap()
+}
+
+fn texture_packer() -> Atlas {
+    Atlas::from_texture_packer(include_str!("data/texturepacker.json"), 0.1).unwrap()
+}
+
+fn indices(atlas: &Atlas, clip: &str) -> Vec<usize> {
+    atlas.clips[clip].frames.iter().map(|frame| frame.index).collect()
+}
+
+#[test]
+fn aseprite_frames_and_trims() {
+    let atlas = aseprite();
+    assert_eq!(atlas.size, PhysicalSize::new(64, 32));
+    assert_eq!(atlas.regions.len(), 6);
+    assert_eq!(atlas.index("knight 4.aseprite"), Some(4));
+    let trimmed = atlas.region("knight 2.aseprite").unwrap();
+    assert!(trimmed.trimmed);
+    assert_eq!(trimmed.rect, Rect::new(vec2(32.0, 0.0), vec2(44.0, 14.0)));
+    assert_eq!(trimmed.offset, vec2(2.0, 2.0));
+    assert_eq!(trimmed.source_size, vec2(16.0, 16.0));
+    assert_eq!(trimmed.pivot, vec2(0.5, 0.5));
+    assert_eq!(trimmed.duration, Some(0.1));
+    assert_eq!(atlas.uv("knight 4.aseprite"), Some(Rect::new(vec2(0.0, 0.5), vec2(0.25, 1.0))));
+}
+
+#[test]
+fn aseprite_tags_become_clips() {
+    let atlas = aseprite();
+    assert_eq!(atlas.clips.len(), 4);
+    let idle = &atlas.clips["idle"];
+    assert_eq!(idle.playback, Playback::Loop);
+    assert_eq!(indices(&atlas, "idle"), [0, 1]);
+    assert_eq!(idle.frames[0].duration, 0.2);
+    let attack = &atlas.clips["attack"];
+    assert_eq!(attack.playback, Playback::Once);
+    assert_eq!(attack.frames.iter().map(|frame| frame.duration).collect::<Vec<_>>(), [0.1, 0.05]);
+    assert_eq!(atlas.clips["walk"].playback, Playback::PingPong);
+    assert_eq!(indices(&atlas, "retreat"), [5, 4]);
+
+    // ready to play
+    let animations = atlas.animations();
+    let mut player = Player::new();
+    player.play("attack");
+    player.update(&animations, 0.1);
+    assert_eq!(player.frame(&animations), Some(3));
+    assert_eq!(player.uv(&animations), atlas.uv("knight 3.aseprite"));
+}
+
+#[test]
+fn aseprite_slices() {
+    let atlas = aseprite();
+    let hitbox = atlas.slice("hitbox").unwrap();
+    assert_eq!(hitbox.keys.len(), 2);
+    assert_eq!(hitbox.key(1).unwrap().bounds, Rect::new(vec2(4.0, 2.0), vec2(12.0, 16.0)));
+    assert_eq!(hitbox.key(1).unwrap().pivot, Some(vec2(4.0, 14.0)));
+    assert_eq!(hitbox.key(5).unwrap().bounds, Rect::new(vec2(2.0, 2.0), vec2(14.0, 16.0)));
+    assert_eq!(hitbox.key(5).unwrap().pivot, None);
+    let panel = atlas.slice("panel").unwrap();
+    assert_eq!(panel.key(0).unwrap().center, Some(Rect::new(vec2(4.0, 4.0), vec2(12.0, 12.0))));
+    assert!(atlas.slice("missing").is_none());
+}
+
+#[test]
+fn aseprite_array_format() {
+    let atlas = Atlas::from_aseprite(include_str!("data/aseprite_array.json")).unwrap();
+    let names: Vec<&str> = atlas.regions.iter().map(|region| region.name.as_str()).collect();
+    assert_eq!(names, ["coin 0.aseprite", "coin 1.aseprite", "coin 2.aseprite"]);
+    let spin = &atlas.clips["spin"];
+    assert_eq!(spin.playback, Playback::PingPong);
+    assert_eq!(indices(&atlas, "spin"), [2, 1, 0]);
+}
+
+#[test]
+fn texture_packer_keeps_the_exported_order() {
+    let atlas = texture_packer();
+    let names: Vec<&str> = atlas.regions.iter().map(|region| region.name.as_str()).collect();
+    assert_eq!(names, ["zombie_walk_0.png", "zombie_walk_1.png", "arrow.png"]);
+    assert_eq!(atlas.size, PhysicalSize::new(64, 34));
+    assert_eq!(atlas.sheet().frames[2], Rect::new(vec2(48.0, 2.0), vec2(62.0, 6.0)));
+    let arrow = atlas.region("arrow.png").unwrap();
+    assert!(!arrow.trimmed);
+    assert_eq!((arrow.pivot, arrow.duration), (vec2(0.0, 0.5), None));
+}
+
+#[test]
+fn texture_packer_animations() {
+    let atlas = texture_packer();
+    let walk = &atlas.clips["zombie_walk"];
+    assert_eq!(walk.playback, Playback::Loop);
+    assert_eq!(indices(&atlas, "zombie_walk"), [0, 1]);
+    assert!(walk.frames.iter().all(|frame| frame.duration == 0.1));
+
+    let broken = include_str!("data/texturepacker.json").replace("\"zombie_walk_1.png\"]", "\"zombie_walk_2.png\"]");
+    assert!(matches!(Atlas::from_texture_packer(&broken, 0.1), Err(AtlasError::Invalid(_))));
+}
+
+#[test]
+fn trimmed_regions_draw_around_their_pivot() {
+    let atlas = texture_packer();
+    // feet pivot, trimmed 6 texels from the left and 2 from the top
+    let zombie = atlas.region("zombie_walk_0.png").unwrap();
+    let drawn = Obb::from_matrix(&zombie.matrix(vec2(100.0, 200.0), 1.0)).bounds();
+    assert_eq!(drawn, Rect::new(vec2(90.0, 170.0), vec2(110.0, 200.0)));
+    let doubled = Obb::from_matrix(&zombie.matrix(vec2(100.0, 200.0), 2.0)).bounds();
+    assert_eq!(doubled, Rect::new(vec2(80.0, 140.0), vec2(120.0, 200.0)));
+    // left middle pivot
+    let arrow = atlas.region("arrow.png").unwrap();
+    let drawn = Obb::from_matrix(&arrow.matrix(vec2(10.0, 10.0), 1.0)).bounds();
+    assert_eq!(drawn, Rect::new(vec2(10.0, 8.0), vec2(24.0, 12.0)));
+}
+
+#[test]
+fn rotated_frames_turn_upright() {
+    let atlas = Atlas::from_texture_packer(include_str!("data/texturepacker_rotated.json"), 0.1).unwrap();
+    let tree = atlas.region("tree.png").unwrap();
+    // packed on its side, 16x32 upright
+    assert!(tree.rotated);
+    assert_eq!(tree.rect, Rect::new(vec2(0.0, 0.0), vec2(32.0, 16.0)));
+    assert_eq!(tree.size(), vec2(16.0, 32.0));
+    assert_eq!(tree.source_size, vec2(16.0, 32.0));
+    assert_eq!(atlas.uv("tree.png"), Some(Rect::new(vec2(0.0, 0.0), vec2(1.0, 1.0))));
+    let drawn = Obb::from_matrix(&tree.matrix(vec2(0.0, 0.0), 1.0));
+    // the texture's top left corner was the sprite's bottom left
+    let expected = [vec2(-8.0, 16.0), vec2(-8.0, -16.0), vec2(8.0, -16.0), vec2(8.0, 16.0)];
+    drawn.corners().into_iter().zip(expected).for_each(|(corner, expected)| assert_within(corner, expected, 1e-4));
+}
+
+#[test]
+fn bad_files_are_errors() {
+    assert!(matches!(Atlas::from_aseprite("{"), Err(AtlasError::Format(_))));
+    assert!(matches!(Atlas::from_aseprite("{\"frames\": {}}"), Err(AtlasError::Format(_))));
+    let texture_packer = include_str!("data/texturepacker.json");
+    assert!(matches!(Atlas::from_texture_packer(texture_packer, 0.0), Err(AtlasError::Invalid(_))));
+    let past_the_end = include_str!("data/aseprite.json").replace("\"from\": 4, \"to\": 5", "\"from\": 4, \"to\": 6");
+    assert!(matches!(Atlas::from_aseprite(&past_the_end), Err(AtlasError::Invalid(_))));
+}
@@ -0,0 +1,41 @@
+// Helpers shared by the test suites, pulled in with `mod common;`. Not every
+// suite uses all of them.
+#![allow(dead_code)]
+
+use graphics2d::geom::Rect;
+use graphics2d::mat::{vec2, vec4, Mat4, Vec2};
+
+pub fn assert_close(a: Vec2, b: Vec2) {
+    assert_within(a, b, 1e-5);
+}
+
+// for suites whose values pick up more rounding than assert_close allows
+pub fn assert_within(a: Vec2, b: Vec2, epsilon: f32) {
+    assert!(a.distance(b) < epsilon, "{:?} != {:?}", a.data, b.data);
+}
+
+pub fn assert_rect(a: Rect, b: Rect) {
+    assert_rect_within(a, b, 1e-5);
+}
+
+pub fn assert_rect_within(a: Rect, b: Rect, epsilon: f32) {
+    assert_within(a.min, b.min, epsilon);
+    assert_within(a.max, b.max, epsilon);
+}
+
+// where a position ends up in clip space
+pub fn clip(matrix: &Mat4, p: Vec2) -> Vec2 {
+    let p = matrix % &vec4(p.x, p.y, 0.0, 1.0);
+    vec2(p.x, p.y)
+}
+
+// small deterministic generator so failures are reproducible, suites add
+// their own helpers for the values they need
+pub struct Lcg(pub u64);
+
+impl Lcg {
+    pub fn next(&mut self) -> f32 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((self.0 >> 40) as f32 / (1u64 << 24) as f32) * 2.0 - 1.0
+    }
+}
@@ -0,0 +1,63 @@
+use graphics2d::{
+    console::CommandRegistry,
+    text::{self, FontInfoContainer},
+};
+
+struct State {
+    count: i32,
+}
+
+fn registry() -> CommandRegistry<State> {
+    let mut commands = CommandRegistry::new();
+    commands.register("add", "adds to the count", |state: &mut State, args: &[&str]| {
+        let amount: i32 = args.first().ok_or("missing amount")?.parse().map_err(|_| "not a number")?;
+        state.count += amount;
+        Ok(state.count.to_string())
+    });
+    commands
+}
+
+#[test]
+fn runs_registered_commands() {
+    let mut commands = registry();
+    let mut state = State { count: 0 };
+    assert_eq!(commands.run(&mut state, "add 2"), Ok("2".to_string()));
+    assert_eq!(commands.run(&mut state, "  add   3 "), Ok("5".to_string()));
+    assert_eq!(commands.run(&mut state, "add x"), Err("not a number".to_string()));
+    assert_eq!(state.count, 5);
+}
+
+#[test]
+fn unknown_and_empty_lines() {
+    let mut commands = registry();
+    let mut state = State { count: 0 };
+    assert!(commands.run(&mut state, "jump").is_err());
+    assert_eq!(commands.run(&mut state, "   "), Ok(String::new()));
+    assert!(commands.run(&mut state, "help").unwrap().contains("add - adds to the count"));
+}
+
+#[test]
+fn variables() {
+    let mut commands = registry();
+    let mut state = State { count: 0 };
+    commands.run(&mut state, "set speed 2.5").unwrap();
+    assert_eq!(commands.var::<f32>("speed"), Some(2.5));
+    assert_eq!(commands.run(&mut state, "get speed"), Ok("2.5".to_string()));
+    assert_eq!(commands.var::<i32>("speed"), None);
+    assert!(commands.run(&mut state, "get missing").is_err());
+    assert!(commands.run(&mut state, "set speed").is_err());
+}
+
+#[test]
+fn splits_lines_on_newlines_and_width() {
+    let font = text::make_font_infos(
+        include_bytes!(env!("SAMPLE_FONT")), &[16.0], text::default_characters().iter(), None, "test".to_string())
+        .unwrap()
+        .remove(0);
+    let lines: Vec<String> = font.split_lines("one\n\ntwo", None).collect();
+    assert_eq!(lines, vec!["one", "", "two"]);
+
+    let word = font.text_width("word");
+    let lines: Vec<String> = font.split_lines("word word word", Some(word * 2.0)).collect();
+    assert_eq!(lines, vec!["word ", "word ", "word"]);
+}
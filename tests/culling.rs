@@ -0,0 +1,76 @@
+use graphics2d::geom::Rect;
+use graphics2d::mat::{vec2, vec4, Mat4, Vec2};
+use graphics2d::rrs::{Entry, FrameStats, Settings};
+use graphics2d::square;
+
+mod common;
+use common::assert_within;
+
+// window sized areas round more than common::assert_close allows
+const EPSILON: f32 = 1e-3;
+
+fn square(matrix: Mat4) -> Entry {
+    Entry::Square(square::RenderParams { matrix, range: 0..6, color: vec4(1.0, 1.0, 1.0, 1.0) })
+}
+
+fn screen(width: u32, height: u32) -> Settings {
+    Settings { projection: Mat4::ortho(winit::dpi::PhysicalSize::new(width, height)), cull: true, snap: None }
+}
+
+#[test]
+fn square_bounds_follow_box2d() {
+    let entry = square(Mat4::box2d(vec2(100.0, 50.0), vec2(20.0, 10.0)));
+    assert_eq!(entry.bounds(), Some(Rect::new(vec2(90.0, 45.0), vec2(110.0, 55.0))));
+
+    let rotated = square(Mat4::box2d_rot(vec2(0.0, 0.0), vec2(2.0, 2.0), std::f32::consts::FRAC_PI_4));
+    let bounds = rotated.bounds().unwrap();
+    assert_within(bounds.max, Vec2::splat(2.0f32.sqrt()), EPSILON);
+    assert_within(bounds.min, Vec2::splat(-(2.0f32.sqrt())), EPSILON);
+}
+
+#[test]
+fn visible_area_of_ortho_is_the_window() {
+    let area = screen(800, 600).visible_area().unwrap();
+    assert_within(area.min, vec2(0.0, 0.0), EPSILON);
+    assert_within(area.max, vec2(800.0, 600.0), EPSILON);
+
+    let settings = Settings { projection: Mat4::ortho_bounds(-10.0, 10.0, -5.0, 5.0, 0.0, 1.0), cull: true, snap: None };
+    let area = settings.visible_area().unwrap();
+    assert_within(area.min, vec2(-10.0, -5.0), EPSILON);
+    assert_within(area.max, vec2(10.0, 5.0), EPSILON);
+
+    let flat = Settings { projection: Mat4::scale2d(vec2(0.0, 1.0)), cull: true, snap: None };
+    assert_eq!(flat.visible_area(), None);
+}
+
+#[test]
+fn entries_off_screen_are_not_visible() {
+    let area = screen(800, 600).visible_area().unwrap();
+    assert!(square(Mat4::box2d(vec2(400.0, 300.0), vec2(100.0, 100.0))).is_visible(&area));
+    // partly on screen on each side
+    assert!(square(Mat4::box2d(vec2(-40.0, 300.0), vec2(100.0, 100.0))).is_visible(&area));
+    assert!(square(Mat4::box2d(vec2(840.0, 300.0), vec2(100.0, 100.0))).is_visible(&area));
+    assert!(square(Mat4::box2d(vec2(400.0, 640.0), vec2(100.0, 100.0))).is_visible(&area));
+    // entirely off screen
+    assert!(!square(Mat4::box2d(vec2(-60.0, 300.0), vec2(100.0, 100.0))).is_visible(&area));
+    assert!(!square(Mat4::box2d(vec2(900.0, 300.0), vec2(100.0, 100.0))).is_visible(&area));
+    assert!(!square(Mat4::box2d(vec2(400.0, -100.0), vec2(100.0, 100.0))).is_visible(&area));
+    // rotated into view
+    let corner = Mat4::box2d_rot(vec2(-60.0, 300.0), vec2(100.0, 100.0), std::f32::consts::FRAC_PI_4);
+    assert!(square(corner).is_visible(&area));
+}
+
+#[test]
+fn entries_without_bounds_are_always_visible() {
+    let area = Rect::new(vec2(0.0, 0.0), vec2(1.0, 1.0));
+    assert_eq!(Entry::Simple.bounds(), None);
+    assert!(Entry::Simple.is_visible(&area));
+}
+
+#[test]
+fn frame_stats_add_up() {
+    let mut stats = FrameStats::default();
+    stats += FrameStats { drawn: 3, culled: 1 };
+    stats += FrameStats { drawn: 2, culled: 4 };
+    assert_eq!(stats, FrameStats { drawn: 5, culled: 5 });
+}
@@ -0,0 +1,94 @@
+use graphics2d::dispatch::{EventDispatcher, Response};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layer {
+    Ui,
+    Console,
+    World,
+}
+
+fn dispatcher() -> EventDispatcher<Layer> {
+    let mut events = EventDispatcher::new();
+    events.register(Layer::World, 0);
+    events.register(Layer::Ui, 10);
+    events.register(Layer::Console, 5);
+    events
+}
+
+#[test]
+fn handlers_run_by_priority() {
+    let events = dispatcher();
+    assert_eq!(events.order().collect::<Vec<_>>(), vec![Layer::Ui, Layer::Console, Layer::World]);
+}
+
+#[test]
+fn equal_priorities_keep_registration_order() {
+    let mut events = EventDispatcher::new();
+    events.register(Layer::World, 0);
+    events.register(Layer::Console, 0);
+    assert_eq!(events.order().collect::<Vec<_>>(), vec![Layer::World, Layer::Console]);
+    events.register(Layer::World, 1);
+    assert_eq!(events.order().collect::<Vec<_>>(), vec![Layer::World, Layer::Console]);
+}
+
+#[test]
+fn focused_handler_goes_first() {
+    let mut events = dispatcher();
+    events.focus(Layer::World);
+    assert_eq!(events.order().collect::<Vec<_>>(), vec![Layer::World, Layer::Ui, Layer::Console]);
+    events.blur();
+    assert_eq!(events.order().next(), Some(Layer::Ui));
+}
+
+#[test]
+fn consumed_stops_propagation() {
+    let mut events = dispatcher();
+    let mut seen = vec![];
+    let state = events.dispatch(|layer| {
+        seen.push(layer);
+        if layer == Layer::Console { Response::Consumed } else { Response::Skipped }
+    });
+    assert!(!state.is_skipped());
+    assert_eq!(seen, vec![Layer::Ui, Layer::Console]);
+}
+
+#[test]
+fn skipped_by_everyone() {
+    let mut events = dispatcher();
+    let mut count = 0;
+    assert!(events.dispatch(|_| { count += 1; Response::Skipped }).is_skipped());
+    assert_eq!(count, 3);
+}
+
+#[test]
+fn focus_moves_through_responses() {
+    let mut events = dispatcher();
+    events.dispatch(|layer| match layer {
+        Layer::World => Response::Focus(Layer::Console),
+        _ => Response::Skipped,
+    });
+    assert_eq!(events.focused(), Some(Layer::Console));
+    events.dispatch(|_| Response::Relinquish);
+    assert_eq!(events.focused(), None);
+}
+
+#[test]
+fn relinquish_from_unfocused_handler_keeps_focus() {
+    let mut events = dispatcher();
+    events.focus(Layer::Console);
+    events.dispatch(|layer| match layer {
+        Layer::Console => Response::Skipped,
+        _ => Response::Relinquish,
+    });
+    assert_eq!(events.focused(), Some(Layer::Console));
+}
+
+#[test]
+fn unregistering_drops_focus() {
+    let mut events = dispatcher();
+    events.focus(Layer::Console);
+    events.unregister(Layer::Console);
+    assert_eq!(events.focused(), None);
+    events.focus(Layer::Console);
+    assert_eq!(events.focused(), None);
+}
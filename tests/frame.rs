@@ -0,0 +1,34 @@
+use graphics2d::frame::{Attachment, FrameError, FrameGraph, Pass};
+
+fn graph(passes: Vec<Pass>) -> FrameGraph {
+    let mut graph = FrameGraph::new();
+    passes.into_iter().for_each(|pass| graph.add_pass(pass));
+    graph
+}
+
+#[test]
+fn valid_graphs_pass() {
+    let graph = graph(vec![
+        Pass::new("post", Attachment::Surface).after("scene"),
+        Pass::new("scene", Attachment::External),
+    ]);
+    assert!(graph.validate().is_ok());
+    assert_eq!(graph.order().unwrap(), vec![1, 0]);
+}
+
+#[test]
+fn validation_finds_what_execute_would_fail_on() {
+    let missing = graph(vec![Pass::new("post", Attachment::Surface).after("scene")]);
+    assert!(matches!(missing.validate(), Err(FrameError::MissingDependency("post", "scene"))));
+
+    let cycle = graph(vec![
+        Pass::new("a", Attachment::External).after("b"),
+        Pass::new("b", Attachment::External).after("a"),
+    ]);
+    assert!(matches!(cycle.validate(), Err(FrameError::Cycle(_))));
+
+    // targets aren't needed to order passes, only to run them
+    let target = graph(vec![Pass::new("minimap", Attachment::Target("minimap"))]);
+    assert!(target.order().is_ok());
+    assert!(matches!(target.validate(), Err(FrameError::MissingTarget("minimap", "minimap"))));
+}
@@ -0,0 +1,290 @@
+use std::f32::consts::FRAC_PI_4;
+
+use graphics2d::geom::{Circle, Obb, Ray, Rect, Segment};
+use graphics2d::mat::{vec2, Mat4, Vec2};
+
+mod common;
+use common::{assert_within, Lcg};
+
+// rotations and intersections round more than common::assert_close allows
+const EPSILON: f32 = 1e-4;
+
+impl Lcg {
+    fn point(&mut self, range: f32) -> Vec2 {
+        vec2(self.next() * range, self.next() * range)
+    }
+
+    fn obb(&mut self) -> Obb {
+        let size = vec2(0.5 + self.next().abs() * 4.0, 0.5 + self.next().abs() * 4.0);
+        Obb::new(self.point(5.0), size, self.next() * 3.0)
+    }
+}
+
+fn unit() -> Rect {
+    Rect::new(vec2(0.0, 0.0), vec2(1.0, 1.0))
+}
+
+#[test]
+fn rect_constructors() {
+    let rect = Rect::from_corners(vec2(4.0, 1.0), vec2(2.0, 3.0));
+    assert_eq!(rect, Rect::new(vec2(2.0, 1.0), vec2(4.0, 3.0)));
+    assert_eq!(Rect::from_center(vec2(3.0, 2.0), vec2(2.0, 2.0)), rect);
+    assert_eq!(Rect::from_pos_size(vec2(2.0, 1.0), vec2(2.0, 2.0)), rect);
+    assert_eq!(Rect::from_points([vec2(3.0, 3.0), vec2(2.0, 2.0), vec2(4.0, 1.0)]), Some(rect));
+    assert_eq!(Rect::from_points([]), None);
+    assert_eq!(rect.center(), vec2(3.0, 2.0));
+    assert_eq!(rect.size(), vec2(2.0, 2.0));
+    assert_eq!(rect.area(), 4.0);
+    assert!(!rect.is_empty());
+    assert!(Rect::new(vec2(1.0, 0.0), vec2(0.0, 1.0)).is_empty());
+}
+
+#[test]
+fn rect_contains_edges() {
+    let rect = unit();
+    assert!(rect.contains(vec2(0.5, 0.5)));
+    assert!(rect.contains(vec2(0.0, 0.0)));
+    assert!(rect.contains(vec2(1.0, 1.0)));
+    assert!(rect.contains(vec2(1.0, 0.3)));
+    assert!(!rect.contains(vec2(1.01, 0.5)));
+    assert!(!rect.contains(vec2(0.5, -0.01)));
+    assert!(rect.contains_rect(&Rect::new(vec2(0.2, 0.2), vec2(1.0, 0.5))));
+    assert!(!rect.contains_rect(&Rect::new(vec2(0.2, 0.2), vec2(1.5, 0.5))));
+}
+
+#[test]
+fn rect_overlap_union_intersection() {
+    let a = unit();
+    let b = Rect::new(vec2(0.5, 0.5), vec2(2.0, 2.0));
+    let touching = Rect::new(vec2(1.0, 0.0), vec2(2.0, 1.0));
+    let apart = Rect::new(vec2(1.5, 0.0), vec2(2.0, 1.0));
+
+    assert!(a.overlaps(&b) && b.overlaps(&a));
+    assert!(a.overlaps(&touching));
+    assert!(!a.overlaps(&apart) && !apart.overlaps(&a));
+
+    assert_eq!(a.intersection(&b), Some(Rect::new(vec2(0.5, 0.5), vec2(1.0, 1.0))));
+    assert_eq!(a.intersection(&touching).map(|rect| rect.area()), Some(0.0));
+    assert_eq!(a.intersection(&apart), None);
+    assert_eq!(a.union(&apart), Rect::new(vec2(0.0, 0.0), vec2(2.0, 1.0)));
+
+    assert_eq!(a.expand_to(vec2(-1.0, 0.5)), Rect::new(vec2(-1.0, 0.0), vec2(1.0, 1.0)));
+    assert_eq!(a.grow(1.0), Rect::new(vec2(-1.0, -1.0), vec2(2.0, 2.0)));
+    assert_eq!(a.translate(vec2(2.0, 3.0)), Rect::new(vec2(2.0, 3.0), vec2(3.0, 4.0)));
+}
+
+#[test]
+fn rect_intersection_agrees_with_overlap() {
+    let mut rng = Lcg(7);
+    for _ in 0..500 {
+        let a = Rect::from_corners(rng.point(3.0), rng.point(3.0));
+        let b = Rect::from_corners(rng.point(3.0), rng.point(3.0));
+        assert_eq!(a.overlaps(&b), a.intersection(&b).is_some());
+        let union = a.union(&b);
+        assert!(union.contains_rect(&a) && union.contains_rect(&b));
+        if let Some(intersection) = a.intersection(&b) {
+            assert!(a.contains_rect(&intersection) && b.contains_rect(&intersection));
+        }
+        let p = rng.point(4.0);
+        assert_eq!(a.contains(p), a.distance(p) == 0.0);
+    }
+}
+
+#[test]
+fn rect_transform_bounds_corners() {
+    let rect = unit();
+    assert_eq!(rect.transform(&Mat4::translate2d(vec2(2.0, 3.0))), rect.translate(vec2(2.0, 3.0)));
+
+    let rotated = rect.transform(&Mat4::rotate2d(FRAC_PI_4));
+    let diagonal = 2.0f32.sqrt();
+    assert_within(rotated.size(), vec2(diagonal, diagonal), EPSILON);
+    assert_within(rotated.center(), vec2(diagonal / 2.0, 0.0), EPSILON);
+
+    let mut rng = Lcg(3);
+    for _ in 0..100 {
+        let matrix = Mat4::box2d_rot(rng.point(10.0), rng.point(3.0), rng.next() * 3.0);
+        let bounds = rect.transform(&matrix);
+        for corner in Obb::from_rect(&rect).transform(&matrix).corners() {
+            assert!(bounds.grow(1e-4).contains(corner));
+        }
+    }
+}
+
+#[test]
+fn circles() {
+    let circle = Circle::new(vec2(0.0, 0.0), 1.0);
+    assert!(circle.contains(vec2(1.0, 0.0)));
+    assert!(!circle.contains(vec2(0.8, 0.8)));
+    assert!(circle.overlaps(&Circle::new(vec2(2.0, 0.0), 1.0)));
+    assert!(!circle.overlaps(&Circle::new(vec2(2.0, 0.1), 1.0)));
+    assert_eq!(circle.bounds(), Rect::new(vec2(-1.0, -1.0), vec2(1.0, 1.0)));
+
+    // near a rect's corner the bounds overlap but the circle doesn't
+    let rect = Rect::new(vec2(0.8, 0.8), vec2(2.0, 2.0));
+    assert!(circle.bounds().overlaps(&rect));
+    assert!(!circle.overlaps_rect(&rect));
+    assert!(circle.overlaps_rect(&Rect::new(vec2(0.7, -0.1), vec2(2.0, 0.1))));
+    assert!(rect.overlaps_circle(&Circle::new(vec2(0.7, 0.7), 0.2)));
+
+    let scaled = circle.transform(&Mat4::box2d(vec2(5.0, 0.0), vec2(2.0, 3.0)));
+    assert_eq!(scaled, Circle::new(vec2(5.0, 0.0), 3.0));
+}
+
+#[test]
+fn segments() {
+    let a = Segment::new(vec2(0.0, 0.0), vec2(2.0, 2.0));
+    let b = Segment::new(vec2(0.0, 2.0), vec2(2.0, 0.0));
+    assert_eq!(a.intersection(&b), Some(vec2(1.0, 1.0)));
+    assert_eq!(a.intersection(&Segment::new(vec2(3.0, 0.0), vec2(0.0, 3.0))), Some(vec2(1.5, 1.5)));
+    assert_eq!(a.intersection(&Segment::new(vec2(5.0, 0.0), vec2(4.0, 1.0))), None);
+    // parallel
+    assert_eq!(a.intersection(&Segment::new(vec2(1.0, 0.0), vec2(3.0, 2.0))), None);
+
+    assert_eq!(a.length(), 8.0f32.sqrt());
+    assert_eq!(a.at(0.5), vec2(1.0, 1.0));
+    assert_eq!(a.closest_point(vec2(2.0, 0.0)), vec2(1.0, 1.0));
+    assert_eq!(a.closest_point(vec2(-3.0, -1.0)), vec2(0.0, 0.0));
+    assert_eq!(a.distance(vec2(4.0, 2.0)), 2.0);
+    assert_eq!(Segment::new(vec2(1.0, 1.0), vec2(1.0, 1.0)).distance(vec2(1.0, 2.0)), 1.0);
+
+    assert!(a.overlaps_rect(&Rect::new(vec2(1.5, -1.0), vec2(3.0, 1.6))));
+    assert!(!a.overlaps_rect(&Rect::new(vec2(1.5, -1.0), vec2(3.0, 1.4))));
+    assert!(a.overlaps_rect(&Rect::new(vec2(-1.0, -1.0), vec2(3.0, 3.0))));
+    assert_eq!(a.transform(&Mat4::translate2d(vec2(1.0, 0.0))).start, vec2(1.0, 0.0));
+}
+
+#[test]
+fn ray_casts() {
+    let ray = Ray::new(vec2(-2.0, 0.5), vec2(1.0, 0.0));
+    assert_eq!(ray.cast_rect(&unit()), Some(2.0));
+    assert_eq!(Ray::new(vec2(0.5, 0.5), vec2(1.0, 0.0)).cast_rect(&unit()), Some(0.0));
+    assert_eq!(Ray::new(vec2(2.0, 0.5), vec2(1.0, 0.0)).cast_rect(&unit()), None);
+    assert_eq!(Ray::new(vec2(-2.0, 1.5), vec2(1.0, 0.0)).cast_rect(&unit()), None);
+    // axis parallel rays inside the slab still hit
+    assert_eq!(Ray::new(vec2(0.5, 3.0), vec2(0.0, -2.0)).cast_rect(&unit()), Some(1.0));
+
+    let circle = Circle::new(vec2(3.0, 0.5), 1.0);
+    assert_eq!(ray.cast_circle(&circle), Some(4.0));
+    assert_eq!(Ray::new(vec2(3.0, 0.0), vec2(1.0, 0.0)).cast_circle(&circle), Some(0.0));
+    assert_eq!(Ray::new(vec2(-2.0, 2.0), vec2(1.0, 0.0)).cast_circle(&circle), None);
+    assert_eq!(Ray::new(vec2(5.0, 0.5), vec2(1.0, 0.0)).cast_circle(&circle), None);
+
+    let wall = Segment::new(vec2(1.0, -1.0), vec2(1.0, 1.0));
+    assert_eq!(ray.cast_segment(&wall), Some(3.0));
+    assert_eq!(Ray::new(vec2(-2.0, 2.0), vec2(1.0, 0.0)).cast_segment(&wall), None);
+    assert_eq!(Ray::new(vec2(2.0, 0.0), vec2(1.0, 0.0)).cast_segment(&wall), None);
+
+    let moved = ray.transform(&Mat4::translate2d(vec2(0.0, 1.0)));
+    assert_eq!(moved, Ray::new(vec2(-2.0, 1.5), vec2(1.0, 0.0)));
+    assert_eq!(ray.at(2.0), vec2(0.0, 0.5));
+}
+
+#[test]
+fn ray_casts_hit_the_surface() {
+    let mut rng = Lcg(11);
+    for _ in 0..300 {
+        let ray = Ray::new(rng.point(10.0), rng.point(1.0));
+        let obb = rng.obb();
+        let rect = obb.bounds();
+        let circle = Circle::new(rng.point(5.0), 0.5 + rng.next().abs() * 2.0);
+        if let Some(t) = ray.cast_rect(&rect) {
+            assert!(rect.grow(1e-3).contains(ray.at(t)));
+            assert!(t == 0.0 || !rect.grow(-1e-3).contains(ray.at(t)));
+        }
+        if let Some(t) = ray.cast_circle(&circle) {
+            assert!(circle.center.distance(ray.at(t)) <= circle.radius + 1e-3);
+        }
+        if let Some(t) = ray.cast_obb(&obb) {
+            let hit = ray.at(t);
+            assert!(obb.contains(hit) || Obb { axes: obb.axes.map(|axis| axis * 1.001), ..obb }.contains(hit));
+        }
+        // walking along the ray finds the box exactly when the cast does
+        let sampled = (0..2000).map(|i| ray.at(i as f32 * 0.01)).any(|p| obb.contains(p));
+        if sampled {
+            assert!(ray.cast_obb(&obb).is_some());
+        }
+    }
+}
+
+#[test]
+fn obb_matches_box2d() {
+    let matrix = Mat4::box2d_rot(vec2(3.0, 4.0), vec2(2.0, 1.0), 0.7);
+    let obb = Obb::new(vec2(3.0, 4.0), vec2(2.0, 1.0), 0.7);
+    let from_matrix = Obb::from_matrix(&matrix);
+    assert_within(from_matrix.center, obb.center, EPSILON);
+    assert_within(from_matrix.axes[0], obb.axes[0], EPSILON);
+    assert_within(from_matrix.axes[1], obb.axes[1], EPSILON);
+
+    let rect = Rect::new(vec2(1.0, 2.0), vec2(5.0, 3.0));
+    let aligned = Obb::from_rect(&rect);
+    assert_eq!(aligned.bounds(), rect);
+    assert_eq!(aligned.corners(), rect.corners());
+    assert!(aligned.contains(vec2(5.0, 3.0)));
+    assert!(!aligned.contains(vec2(5.1, 3.0)));
+}
+
+#[test]
+fn obb_contains_rotated_points() {
+    let obb = Obb::new(vec2(0.0, 0.0), vec2(4.0, 1.0), FRAC_PI_4);
+    // counterclockwise on screen, so up and to the right with y down
+    assert!(obb.contains(vec2(1.0, -1.0)));
+    assert!(obb.contains(vec2(-1.3, 1.3)));
+    assert!(!obb.contains(vec2(1.0, 1.0)));
+    assert!(!obb.contains(vec2(1.5, 0.0)));
+    assert!(obb.bounds().contains(vec2(1.5, 0.0)));
+
+    let flat = Obb { center: vec2(0.0, 0.0), axes: [vec2(1.0, 0.0), vec2(2.0, 0.0)] };
+    assert!(!flat.contains(vec2(0.0, 0.0)));
+}
+
+#[test]
+fn obb_overlap_matches_sampling() {
+    let mut rng = Lcg(5);
+    for _ in 0..300 {
+        let a = rng.obb();
+        let b = rng.obb();
+        assert_eq!(a.overlaps(&b), b.overlaps(&a));
+        // any corner of one inside the other means they overlap
+        if a.corners().iter().any(|corner| b.contains(*corner)) {
+            assert!(a.overlaps(&b));
+        }
+        // a grid over a finds a point in b whenever they overlap by a margin
+        let shrunk = |obb: &Obb| Obb { axes: obb.axes.map(|axis| axis * 0.97), ..*obb };
+        let sampled = (0..=40).flat_map(|u| (0..=40).map(move |v| (u, v)))
+            .map(|(u, v)| a.center + a.axes[0] * (u as f32 / 20.0 - 1.0) + a.axes[1] * (v as f32 / 20.0 - 1.0))
+            .any(|p| b.contains(p));
+        if sampled {
+            assert!(a.overlaps(&b));
+        } else {
+            assert!(!shrunk(&a).overlaps(&shrunk(&b)));
+        }
+    }
+}
+
+#[test]
+fn obb_against_rects_and_circles() {
+    let obb = Obb::new(vec2(0.0, 0.0), vec2(4.0, 1.0), FRAC_PI_4);
+    // in the box's bounds but off to the side of the rotated box
+    let corner = Rect::new(vec2(1.0, 1.0), vec2(2.0, 2.0));
+    assert!(obb.bounds().overlaps(&corner));
+    assert!(!obb.overlaps_rect(&corner));
+    assert!(obb.overlaps_rect(&Rect::new(vec2(1.0, -3.0), vec2(3.0, -1.0))));
+
+    assert!(obb.overlaps_circle(&Circle::new(vec2(0.0, 0.0), 0.1)));
+    assert!(obb.overlaps_circle(&Circle::new(vec2(1.0, 0.0), 0.4)));
+    assert!(!obb.overlaps_circle(&Circle::new(vec2(1.5, 0.5), 0.4)));
+}
+
+#[test]
+fn obb_transform_is_exact() {
+    let mut rng = Lcg(9);
+    for _ in 0..100 {
+        let obb = rng.obb();
+        let matrix = Mat4::box2d_rot(rng.point(10.0), rng.point(3.0), rng.next() * 3.0);
+        let transformed = obb.transform(&matrix);
+        let expected = Obb::from_matrix(&(matrix * Mat4::box2d_rot(obb.center, vec2(obb.axes[0].length(), obb.axes[1].length()) * 2.0, obb.axes[0].angle())));
+        for (a, b) in transformed.corners().iter().zip(expected.corners()) {
+            assert_within(*a, b, EPSILON);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use graphics2d::text::{self, cache::GlyphCache, BaseFontInfoContainer};
+
+fn cache(page_size: u32, max_pages: usize) -> GlyphCache {
+    GlyphCache::new(include_bytes!(env!("SAMPLE_FONT")), 16.0, page_size, max_pages, "cache".to_string()).unwrap()
+}
+
+#[test]
+fn rasterizes_glyphs_on_first_use() {
+    let mut cache = cache(256, 1);
+    assert!(cache.get_metrics(&'a').is_none());
+    cache.prepare(1, "abca");
+    assert!(cache.contains('a') && cache.contains('b') && cache.contains('c'));
+    assert!(!cache.contains('d'));
+    // one upload per new glyph
+    assert_eq!(cache.take_uploads().len(), 3);
+    cache.prepare(2, "cab");
+    assert!(cache.take_uploads().is_empty());
+}
+
+#[test]
+fn matches_static_atlas_metrics() {
+    let mut cache = cache(256, 1);
+    let chars = text::default_characters();
+    let atlas = text::make_font_infos(include_bytes!(env!("SAMPLE_FONT")), &[16.0], chars.iter(), None, "atlas".to_string())
+        .unwrap()
+        .remove(0);
+    cache.prepare(1, "Wg ");
+    for c in ['W', 'g', ' '] {
+        let (a, b) = (atlas.get_metrics(&c).unwrap(), cache.get_metrics(&c).unwrap());
+        assert_eq!(a.glyph_size.x, b.glyph_size.x);
+        assert_eq!(a.glyph_size.y, b.glyph_size.y);
+        assert_eq!(a.advance, b.advance);
+        assert_eq!(a.tsb, b.tsb);
+    }
+    assert_eq!(cache.line_height(), atlas.line_height());
+    assert_eq!(cache.ascent(), atlas.ascent());
+}
+
+#[test]
+fn uploads_cover_padded_glyphs() {
+    let mut cache = cache(256, 1);
+    cache.prepare(1, "M");
+    let upload = cache.take_uploads().remove(0);
+    let metrics = cache.get_metrics(&'M').unwrap();
+    assert_eq!(upload.page, 0);
+    assert_eq!(upload.origin, [metrics.glyph_pos.x as u32, metrics.glyph_pos.y as u32]);
+    assert_eq!(upload.size, [metrics.glyph_size.x as u32 + 1, metrics.glyph_size.y as u32 + 1]);
+    assert_eq!(upload.data.len(), (upload.size[0] * upload.size[1]) as usize);
+    // the padding column is cleared
+    assert!(upload.data.chunks(upload.size[0] as usize).all(|row| *row.last().unwrap() == 0));
+}
+
+#[test]
+fn glyphs_never_overlap() {
+    let mut cache = cache(64, 2);
+    cache.prepare(1, "abcdefghijklmnopqrstuvwxyzABCDEFGHIJ");
+    let uploads = cache.take_uploads();
+    for (i, a) in uploads.iter().enumerate() {
+        for b in &uploads[i + 1..] {
+            let overlap_x = a.origin[0] < b.origin[0] + b.size[0] && b.origin[0] < a.origin[0] + a.size[0];
+            let overlap_y = a.origin[1] < b.origin[1] + b.size[1] && b.origin[1] < a.origin[1] + a.size[1];
+            assert!(a.page != b.page || !(overlap_x && overlap_y));
+        }
+    }
+}
+
+#[test]
+fn adds_pages_when_full() {
+    let mut cache = cache(32, 4);
+    cache.prepare(1, "abcdefgh");
+    assert!(cache.page_count() > 1);
+    assert!(cache.page_count() <= 4);
+    assert!("abcdefgh".chars().all(|c| cache.contains(c)));
+}
+
+#[test]
+fn evicts_least_recently_used_glyphs() {
+    let mut cache = cache(32, 1);
+    cache.prepare(1, "abc");
+    cache.prepare(2, "def");
+    cache.prepare(3, "abc");
+    // filling the page again has to push out the glyphs from frame 2 first
+    let mut frame = 4;
+    while ["d", "e", "f"].iter().all(|c| cache.contains(c.chars().next().unwrap())) {
+        cache.prepare(frame, &char::from_u32('g' as u32 + frame as u32 - 4).unwrap().to_string());
+        frame += 1;
+        assert!(frame < 30, "page never filled up");
+    }
+    assert!(cache.contains('a') && cache.contains('b') && cache.contains('c'));
+}
+
+#[test]
+fn keeps_glyphs_used_this_frame() {
+    let mut cache = cache(32, 1);
+    let text = "abcdefghijklmnopqrstuvwxyz";
+    cache.prepare(1, text);
+    // the page is too small for all of them, later glyphs are left out rather
+    // than replacing ones already drawn this frame
+    let cached: Vec<char> = text.chars().filter(|c| cache.contains(*c)).collect();
+    assert!(cached.len() < text.len());
+    assert_eq!(cached, text.chars().take(cached.len()).collect::<Vec<_>>());
+}
+
+#[test]
+fn falls_back_to_not_found_char() {
+    let mut cache = cache(64, 1);
+    cache.not_found_char = Some('?');
+    cache.prepare(1, "\u{e000}");
+    assert!(!cache.contains('\u{e000}'));
+    assert!(cache.contains('?'));
+    assert_eq!(cache.get_metrics(&'\u{e000}').unwrap().advance, cache.get_metrics(&'?').unwrap().advance);
+    assert_eq!(cache.page('\u{e000}'), Some(0));
+}
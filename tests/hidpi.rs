@@ -0,0 +1,51 @@
+use graphics2d::geom::Rect;
+use graphics2d::mat::{vec2, Mat4};
+use graphics2d::projection::Projection;
+use graphics2d::util::ScaleVariants;
+use winit::dpi::PhysicalSize;
+
+mod common;
+use common::{assert_close, clip};
+
+#[test]
+fn ortho_logical_counts_logical_pixels() {
+    let window = PhysicalSize::new(2560, 1440);
+    let matrix = Mat4::ortho_logical(window, 2.0);
+    assert_close(clip(&matrix, vec2(0.0, 0.0)), vec2(-1.0, 1.0));
+    assert_close(clip(&matrix, vec2(1280.0, 720.0)), vec2(1.0, -1.0));
+    assert_close(clip(&matrix, vec2(640.0, 360.0)), vec2(0.0, 0.0));
+    // the same as ortho at a scale factor of 1
+    assert_eq!(Mat4::ortho_logical(window, 1.0).data, Mat4::ortho(window).data);
+    let fractional = Mat4::ortho_logical(PhysicalSize::new(1500, 900), 1.5);
+    assert_close(clip(&fractional, vec2(1000.0, 600.0)), vec2(1.0, -1.0));
+}
+
+#[test]
+fn logical_projection_covers_the_window() {
+    let window = PhysicalSize::new(2560, 1440);
+    let projection = Projection::logical(window, 2.0);
+    assert_eq!(projection.viewport, Rect::new(vec2(0.0, 0.0), vec2(2560.0, 1440.0)));
+    assert_eq!(projection.visible, Rect::new(vec2(0.0, 0.0), vec2(1280.0, 720.0)));
+    assert_eq!(projection.matrix.data, Mat4::ortho_logical(window, 2.0).data);
+    // window positions are physical, like InputState::mouse_position
+    assert_close(projection.window_to_design(vec2(200.0, 100.0)).unwrap(), vec2(100.0, 50.0));
+    assert_close(projection.design_to_window(vec2(100.0, 50.0)), vec2(200.0, 100.0));
+}
+
+#[test]
+fn variants_pick_the_smallest_that_is_sharp() {
+    let mut variants = ScaleVariants::new();
+    assert_eq!(variants.pick(1.0), None);
+    variants.add(2.0, "@2x");
+    variants.add(1.0, "@1x");
+    variants.add(4.0, "@4x");
+    assert_eq!(variants.len(), 3);
+    assert_eq!(variants.pick(1.0), Some((1.0, &"@1x")));
+    assert_eq!(variants.pick(1.25), Some((2.0, &"@2x")));
+    assert_eq!(variants.pick(2.0), Some((2.0, &"@2x")));
+    assert_eq!(variants.pick(3.0), Some((4.0, &"@4x")));
+    // past the largest, it's stretched
+    assert_eq!(variants.pick(8.0), Some((4.0, &"@4x")));
+    // below the smallest, it's shrunk
+    assert_eq!(variants.pick(0.5), Some((1.0, &"@1x")));
+}
@@ -0,0 +1,131 @@
+use std::collections::{HashMap, HashSet};
+
+use graphics2d::{
+    mat::{vec2, vec4},
+    text::{
+        layout::{layout, Align, LayoutSettings, Span},
+        FontMetricsInfo, GlyphMetrics,
+    },
+};
+
+// Every glyph is 8x10 with a 1px bearing and a 10px advance
+fn mono_font(scale: f32) -> FontMetricsInfo {
+    let char_data = (' '..='~')
+        .map(|c| {
+            let size = if c == ' ' { vec2(0.0, 0.0) } else { vec2(8.0 * scale, 10.0 * scale) };
+            (c, GlyphMetrics {
+                glyph_pos: vec2(0.0, 0.0),
+                glyph_size: size,
+                advance: 10.0 * scale,
+                lsb: scale,
+                tsb: 10.0 * scale,
+            })
+        })
+        .collect();
+    FontMetricsInfo {
+        char_data,
+        font_size: 16.0 * scale,
+        height: 16.0 * scale,
+        ascent: 12.0 * scale,
+        kerning: HashMap::from([(('A', 'V'), -2.0 * scale)]),
+        not_found_char: None,
+        ignore_chars: HashSet::new(),
+    }
+}
+
+fn span(text: &str) -> Span<'_> {
+    Span { text, font: 0, color: vec4(1.0, 1.0, 1.0, 1.0) }
+}
+
+fn xs(layout: &graphics2d::text::layout::Layout, line: usize) -> Vec<f32> {
+    layout.glyphs[layout.lines[line].glyphs.clone()].iter().map(|glyph| glyph.position.x).collect()
+}
+
+#[test]
+fn single_line_uses_advance_and_bearings() {
+    let result = layout(&[mono_font(1.0)], &[span("ab c")], &LayoutSettings::default());
+    assert_eq!(result.lines.len(), 1);
+    assert_eq!(xs(&result, 0), vec![1.0, 11.0, 31.0]);
+    assert_eq!(result.glyphs[0].position.y, 2.0);
+    assert_eq!(result.width, 39.0);
+    assert_eq!(result.height, 16.0);
+}
+
+#[test]
+fn kerning_pairs_shift_the_second_glyph() {
+    let fonts = [mono_font(1.0)];
+    let kerned = layout(&fonts, &[span("AV")], &LayoutSettings::default());
+    assert_eq!(xs(&kerned, 0), vec![1.0, 9.0]);
+    let settings = LayoutSettings { kerning: false, ..Default::default() };
+    let plain = layout(&fonts, &[span("AV")], &settings);
+    assert_eq!(xs(&plain, 0), vec![1.0, 11.0]);
+}
+
+#[test]
+fn newlines_start_new_lines() {
+    let result = layout(&[mono_font(1.0)], &[span("ab\n\ncd")], &LayoutSettings::default());
+    assert_eq!(result.lines.len(), 3);
+    assert!(result.lines[1].glyphs.is_empty());
+    assert_eq!(result.lines[2].top, 32.0);
+    assert_eq!(result.lines[2].baseline, 44.0);
+    assert_eq!(xs(&result, 2), vec![1.0, 11.0]);
+}
+
+#[test]
+fn wraps_at_word_boundaries() {
+    let settings = LayoutSettings { max_width: Some(60.0), ..Default::default() };
+    let result = layout(&[mono_font(1.0)], &[span("aaa bbb ccc")], &settings);
+    assert_eq!(result.lines.len(), 3);
+    assert_eq!(xs(&result, 1), vec![1.0, 11.0, 21.0]);
+    assert_eq!(result.lines[0].width, 29.0);
+}
+
+#[test]
+fn splits_words_wider_than_the_line() {
+    let settings = LayoutSettings { max_width: Some(30.0), ..Default::default() };
+    let result = layout(&[mono_font(1.0)], &[span("abcdefg")], &settings);
+    let counts: Vec<usize> = result.lines.iter().map(|line| line.glyphs.len()).collect();
+    assert_eq!(counts, vec![3, 3, 1]);
+}
+
+#[test]
+fn alignment_offsets_lines() {
+    let fonts = [mono_font(1.0)];
+    let aligned = |align| {
+        let settings = LayoutSettings { max_width: Some(100.0), align, ..Default::default() };
+        layout(&fonts, &[span("ab")], &settings).lines[0].offset
+    };
+    assert_eq!(aligned(Align::Left), 0.0);
+    assert_eq!(aligned(Align::Right), 81.0);
+    assert_eq!(aligned(Align::Center), 40.5);
+}
+
+#[test]
+fn justify_stretches_wrapped_lines_only() {
+    let settings = LayoutSettings { max_width: Some(60.0), align: Align::Justify, ..Default::default() };
+    let result = layout(&[mono_font(1.0)], &[span("a b c d e")], &settings);
+    assert_eq!(result.lines.len(), 2);
+    // "a b c" is 49 wide, the 11px left over goes into its two gaps
+    assert_eq!(xs(&result, 0), vec![1.0, 26.5, 52.0]);
+    assert_eq!(result.lines[0].width, 60.0);
+    assert_eq!(xs(&result, 1), vec![1.0, 21.0]);
+}
+
+#[test]
+fn spans_change_color_and_size() {
+    let fonts = [mono_font(1.0), mono_font(2.0)];
+    let red = vec4(1.0, 0.0, 0.0, 1.0);
+    let spans = [span("a"), Span { text: "b", font: 1, color: red }];
+    let settings = LayoutSettings { line_spacing: 1.5, ..Default::default() };
+    let result = layout(&fonts, &spans, &settings);
+    assert_eq!(result.glyphs[1].font, 1);
+    assert_eq!(result.glyphs[1].span, 1);
+    assert_eq!(result.glyphs[1].color.x, 1.0);
+    assert_eq!(result.glyphs[1].color.y, 0.0);
+    assert_eq!(result.glyphs[1].size.y, 20.0);
+    // the line takes the tallest font's metrics
+    assert_eq!(result.lines[0].baseline, 24.0);
+    assert_eq!(result.height, 48.0);
+    assert_eq!(result.glyphs[0].position.y, 14.0);
+    assert_eq!(result.glyphs[1].position.x, 12.0);
+}
@@ -0,0 +1,165 @@
+use graphics2d::mat::{vec2, vec3, vec4, Mat3, Mat4};
+
+mod common;
+use common::Lcg;
+
+impl Lcg {
+    fn mat4(&mut self) -> Mat4 {
+        Mat4 { data: std::array::from_fn(|_| std::array::from_fn(|_| self.next() * 4.0)) }
+    }
+
+    // random scale, rotation and translation, always invertible
+    fn affine(&mut self) -> Mat3 {
+        let size = vec2(0.25 + self.next().abs() * 4.0, 0.25 + self.next().abs() * 4.0);
+        Mat3::box2d_rot(vec2(self.next() * 100.0, self.next() * 100.0), size, self.next() * 3.0)
+    }
+}
+
+fn assert_close4(a: &Mat4, b: &Mat4, epsilon: f32) {
+    for c in 0..4 {
+        for r in 0..4 {
+            assert!((a.data[c][r] - b.data[c][r]).abs() <= epsilon, "{:?} != {:?}", a, b);
+        }
+    }
+}
+
+fn assert_close3(a: &Mat3, b: &Mat3, epsilon: f32) {
+    for c in 0..3 {
+        for r in 0..3 {
+            assert!((a.data[c][r] - b.data[c][r]).abs() <= epsilon, "{:?} != {:?}", a, b);
+        }
+    }
+}
+
+#[test]
+fn inverse_undoes_the_matrix() {
+    let mut rng = Lcg(1);
+    for _ in 0..200 {
+        let m = rng.mat4();
+        // skip nearly singular matrices, their inverses lose all precision
+        if m.determinant().abs() < 1.0 {
+            continue;
+        }
+        let inverse = m.inverse().unwrap();
+        assert_close4(&(m * inverse), &Mat4::identity(), 1e-3);
+        assert_close4(&(inverse * m), &Mat4::identity(), 1e-3);
+    }
+}
+
+#[test]
+fn singular_matrices_have_no_inverse() {
+    assert!(Mat4::scale2d(vec2(0.0, 1.0)).inverse().is_none());
+    let mut m = Mat4::identity();
+    m.data[1] = m.data[0];
+    assert!(m.inverse().is_none());
+    assert_eq!(m.determinant(), 0.0);
+}
+
+#[test]
+fn transpose_is_an_involution() {
+    let mut rng = Lcg(2);
+    for _ in 0..50 {
+        let (a, b) = (rng.mat4(), rng.mat4());
+        assert_close4(&a.transpose().transpose(), &a, 0.0);
+        // (ab)^T = b^T a^T
+        assert_close4(&(a * b).transpose(), &(b.transpose() * a.transpose()), 1e-4);
+        assert!((a.transpose().determinant() - a.determinant()).abs() <= 1e-2);
+    }
+}
+
+#[test]
+fn determinant_is_multiplicative() {
+    let mut rng = Lcg(3);
+    for _ in 0..50 {
+        let (a, b) = (rng.mat4(), rng.mat4());
+        let (ab, product) = ((a * b).determinant(), a.determinant() * b.determinant());
+        assert!((ab - product).abs() <= 1e-3 * product.abs().max(1.0), "{} != {}", ab, product);
+    }
+    assert_eq!(Mat4::scale(2.0).determinant(), 8.0);
+    assert_eq!(Mat4::translate2d(vec2(5.0, 6.0)).determinant(), 1.0);
+}
+
+#[test]
+fn inverse_maps_screen_points_back() {
+    let projection = Mat4::ortho(winit::dpi::PhysicalSize::new(800, 600));
+    let model = Mat4::box2d_rot(vec2(300.0, 200.0), vec2(50.0, 20.0), 0.7);
+    let m = projection * model;
+    let point = vec4(0.3, -0.2, 0.0, 1.0);
+    let clip = m % point;
+    let back = m.inverse().unwrap() % clip;
+    for i in 0..4 {
+        assert!((back.data[i] - point.data[i]).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn ortho_bounds_matches_ortho() {
+    let ortho = Mat4::ortho(winit::dpi::PhysicalSize::new(800, 600));
+    assert_close4(&Mat4::ortho_bounds(0.0, 800.0, 600.0, 0.0, 0.0, 1.0), &ortho, 1e-6);
+    let m = Mat4::ortho_bounds(-10.0, 30.0, -5.0, 15.0, 1.0, 3.0);
+    let corner = m % vec4(30.0, 15.0, 3.0, 1.0);
+    assert_eq!(corner.data, [1.0, 1.0, 1.0, 1.0]);
+    let corner = m % vec4(-10.0, -5.0, 1.0, 1.0);
+    assert_eq!(corner.data, [-1.0, -1.0, 0.0, 1.0]);
+}
+
+#[test]
+fn look_at_faces_down_negative_z() {
+    let eye = vec3(1.0, 2.0, 3.0);
+    let view = Mat4::look_at(eye, vec3(1.0, 2.0, -10.0), vec3(0.0, 1.0, 0.0));
+    let origin = view % vec4(1.0, 2.0, 3.0, 1.0);
+    assert!(origin.data[..3].iter().all(|v| v.abs() < 1e-6));
+    let ahead = view % vec4(1.0, 2.0, -2.0, 1.0);
+    assert!((ahead.z + 5.0).abs() < 1e-5);
+    let up = view % vec4(1.0, 3.0, 3.0, 1.0);
+    assert!((up.y - 1.0).abs() < 1e-6);
+    // view matrices are rigid, so the inverse is cheap to check
+    let mut rng = Lcg(4);
+    for _ in 0..20 {
+        let eye = vec3(rng.next() * 10.0, rng.next() * 10.0, rng.next() * 10.0);
+        let target = vec3(rng.next() * 10.0, rng.next() * 10.0, rng.next() * 10.0);
+        let view = Mat4::look_at(eye, target, vec3(0.0, 0.0, 1.0));
+        assert!((view.determinant() - 1.0).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn mat3_inverse_undoes_the_transform() {
+    let mut rng = Lcg(5);
+    for _ in 0..200 {
+        let m = rng.affine();
+        let inverse = m.inverse().unwrap();
+        assert_close3(&(m * inverse), &Mat3::identity(), 1e-4);
+        let p = vec2(rng.next() * 50.0, rng.next() * 50.0);
+        let back = inverse.transform_point(m.transform_point(p));
+        assert!((back.x - p.x).abs() < 1e-3 && (back.y - p.y).abs() < 1e-3);
+    }
+    assert!(Mat3::scale2d(vec2(1.0, 0.0)).inverse().is_none());
+}
+
+#[test]
+fn mat3_matches_mat4() {
+    let mut rng = Lcg(6);
+    for _ in 0..100 {
+        let pos = vec2(rng.next() * 100.0, rng.next() * 100.0);
+        let size = vec2(rng.next() * 10.0, rng.next() * 10.0);
+        let rotation = rng.next() * 3.0;
+        let (a, b) = (Mat3::box2d_rot(pos, size, rotation), rng.affine());
+        assert_close4(&Mat4::from(a * b), &(Mat4::box2d_rot(pos, size, rotation) * b.to_mat4()), 1e-3);
+        let p = vec2(rng.next(), rng.next());
+        let expected = Mat4::from(a) % vec4(p.x, p.y, 0.0, 1.0);
+        let actual = a.transform_point(p);
+        assert!((actual.x - expected.x).abs() < 1e-4 && (actual.y - expected.y).abs() < 1e-4);
+        assert!((a.determinant() - Mat4::from(a).determinant()).abs() < 1e-2);
+        assert_close3(&a.transpose().transpose(), &a, 0.0);
+    }
+}
+
+#[test]
+fn mat3_vectors_ignore_translation() {
+    let m = Mat3::translate2d(vec2(5.0, 5.0)) * Mat3::scale2d(vec2(2.0, 3.0));
+    let v = m.transform_vector(vec2(1.0, 1.0));
+    assert_eq!((v.x, v.y), (2.0, 3.0));
+    let p = m.transform_point(vec2(1.0, 1.0));
+    assert_eq!((p.x, p.y), (7.0, 8.0));
+}
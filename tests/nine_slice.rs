@@ -0,0 +1,95 @@
+use graphics2d::geom::Rect;
+use graphics2d::mat::{vec2, Vec2};
+use graphics2d::textured::{Insets, NineSlice, Piece, SliceMode};
+
+mod common;
+use common::{assert_close, assert_rect};
+
+fn rect(x: f32, y: f32, right: f32, bottom: f32) -> Rect {
+    Rect::new(vec2(x, y), vec2(right, bottom))
+}
+
+// a 30x30 texture with 10 texel borders in a 100x60 panel
+fn panel(mode: SliceMode) -> NineSlice {
+    NineSlice {
+        uv: Rect::UNIT,
+        size: vec2(30.0, 30.0),
+        insets: Insets::uniform(10.0),
+        target: rect(0.0, 0.0, 100.0, 60.0),
+        scale: 1.0,
+        mode,
+    }
+}
+
+#[test]
+fn stretching_keeps_the_corners() {
+    let pieces = panel(SliceMode::Stretch).pieces();
+    assert_eq!(pieces.len(), 9);
+    assert_rect(pieces[0].target, rect(0.0, 0.0, 10.0, 10.0));
+    assert_rect(pieces[0].uv, rect(0.0, 0.0, 1.0 / 3.0, 1.0 / 3.0));
+    assert_rect(pieces[4].target, rect(10.0, 10.0, 90.0, 50.0));
+    assert_rect(pieces[4].uv, rect(1.0 / 3.0, 1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0));
+    assert_rect(pieces[8].target, rect(90.0, 50.0, 100.0, 60.0));
+    assert_rect(pieces[8].uv, rect(2.0 / 3.0, 2.0 / 3.0, 1.0, 1.0));
+    assert!(pieces.iter().all(|piece| piece.tiles == vec2(1.0, 1.0)));
+    // the pieces cover the target exactly
+    let area: f32 = pieces.iter().map(|piece| piece.target.area()).sum();
+    assert!((area - 6000.0).abs() < 1e-3);
+}
+
+#[test]
+fn tiling_repeats_edges_and_center() {
+    let tiles: Vec<Vec2> = panel(SliceMode::Tile).pieces().iter().map(|piece| piece.tiles).collect();
+    assert_eq!(tiles, [
+        vec2(1.0, 1.0), vec2(8.0, 1.0), vec2(1.0, 1.0),
+        vec2(1.0, 4.0), vec2(8.0, 4.0), vec2(1.0, 4.0),
+        vec2(1.0, 1.0), vec2(8.0, 1.0), vec2(1.0, 1.0),
+    ]);
+    // repeats are counted in scaled texels, and can end partway
+    let scaled = NineSlice { scale: 2.0, target: rect(0.0, 0.0, 110.0, 60.0), ..panel(SliceMode::Tile) };
+    let center = scaled.pieces()[4];
+    assert_rect(center.target, rect(20.0, 20.0, 90.0, 40.0));
+    assert_close(center.tiles, vec2(3.5, 1.0));
+}
+
+#[test]
+fn small_targets_shrink_the_borders() {
+    let narrow = NineSlice { target: rect(0.0, 0.0, 10.0, 60.0), ..panel(SliceMode::Stretch) };
+    let pieces = narrow.pieces();
+    // no room left for the middle column
+    assert_eq!(pieces.len(), 6);
+    assert_rect(pieces[0].target, rect(0.0, 0.0, 5.0, 10.0));
+    assert_rect(pieces[1].target, rect(5.0, 0.0, 10.0, 10.0));
+    // the texture isn't cut, only squashed
+    assert_rect(pieces[0].uv, rect(0.0, 0.0, 1.0 / 3.0, 1.0 / 3.0));
+}
+
+#[test]
+fn regions_of_a_texture() {
+    // the top right quarter of a 32x32 texture
+    let nine_slice = NineSlice {
+        uv: rect(0.5, 0.0, 1.0, 0.5),
+        size: vec2(16.0, 16.0),
+        insets: Insets { left: 4.0, top: 2.0, right: 4.0, bottom: 6.0 },
+        target: rect(100.0, 100.0, 200.0, 200.0),
+        scale: 1.0,
+        mode: SliceMode::Stretch,
+    };
+    let pieces = nine_slice.pieces();
+    assert_rect(pieces[0].uv, rect(0.5, 0.0, 0.625, 1.0 / 16.0));
+    assert_rect(pieces[8].uv, rect(0.875, 5.0 / 16.0, 1.0, 0.5));
+    assert_rect(pieces[8].target, rect(196.0, 194.0, 200.0, 200.0));
+}
+
+#[test]
+fn insets_without_borders() {
+    let plain = NineSlice { insets: Insets::uniform(0.0), ..panel(SliceMode::Tile) };
+    assert_eq!(plain.pieces(), [Piece { target: rect(0.0, 0.0, 100.0, 60.0), uv: Rect::UNIT, tiles: vec2(100.0 / 30.0, 2.0) }]);
+}
+
+#[test]
+fn insets_from_a_center_rect() {
+    // like an Aseprite slice's center
+    let insets = Insets::from_center(vec2(16.0, 24.0), rect(3.0, 4.0, 12.0, 20.0));
+    assert_eq!(insets, Insets { left: 3.0, top: 4.0, right: 4.0, bottom: 4.0 });
+}
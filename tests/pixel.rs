@@ -0,0 +1,80 @@
+use graphics2d::geom::{Obb, Rect};
+use graphics2d::mat::{vec2, Mat4, Vec2};
+use graphics2d::pixel::{integer_scale, letterbox, window_to_virtual};
+use graphics2d::rrs::Settings;
+use winit::dpi::PhysicalSize;
+
+fn size(width: u32, height: u32) -> PhysicalSize<u32> {
+    PhysicalSize::new(width, height)
+}
+
+fn assert_whole(p: Vec2) {
+    assert!((p.x - p.x.round()).abs() < 1e-3 && (p.y - p.y.round()).abs() < 1e-3, "{:?} isn't whole", p.data);
+}
+
+#[test]
+fn integer_scale_fits_the_smaller_axis() {
+    assert_eq!(integer_scale(size(320, 180), size(1280, 720)), 4);
+    assert_eq!(integer_scale(size(320, 180), size(1919, 1080)), 5);
+    // wide windows are limited by height
+    assert_eq!(integer_scale(size(320, 180), size(3000, 400)), 2);
+    // never shrinks
+    assert_eq!(integer_scale(size(320, 180), size(200, 100)), 1);
+    assert_eq!(integer_scale(size(320, 180), size(0, 0)), 1);
+}
+
+#[test]
+fn letterbox_centers_on_whole_pixels() {
+    assert_eq!(letterbox(size(320, 180), size(1280, 720)), Rect::new(vec2(0.0, 0.0), vec2(1280.0, 720.0)));
+    // 3x leaves 320 pixels across and 180 down, split between both sides
+    assert_eq!(letterbox(size(320, 180), size(1280, 720 - 1)), Rect::new(vec2(160.0, 89.0), vec2(1120.0, 629.0)));
+    let odd = letterbox(size(320, 180), size(1001, 601));
+    assert_eq!(odd.size(), vec2(960.0, 540.0));
+    assert_eq!(odd.min, vec2(20.0, 30.0));
+    // too small a window crops evenly
+    assert_eq!(letterbox(size(320, 180), size(300, 180)), Rect::new(vec2(-10.0, 0.0), vec2(310.0, 180.0)));
+}
+
+#[test]
+fn window_positions_map_to_texels() {
+    let window = size(1280, 719);
+    assert_eq!(window_to_virtual(size(320, 180), window, vec2(160.0, 89.0)), Some(vec2(0.0, 0.0)));
+    assert_eq!(window_to_virtual(size(320, 180), window, vec2(640.0, 359.0)), Some(vec2(160.0, 90.0)));
+    assert_eq!(window_to_virtual(size(320, 180), window, vec2(1120.0, 629.0)), Some(vec2(320.0, 180.0)));
+    // over the bars
+    assert_eq!(window_to_virtual(size(320, 180), window, vec2(100.0, 300.0)), None);
+    assert_eq!(window_to_virtual(size(320, 180), window, vec2(640.0, 700.0)), None);
+}
+
+#[test]
+fn snapping_moves_corners_onto_pixels() {
+    let settings = Settings { projection: Mat4::ortho(size(320, 180)), cull: true, snap: Some(size(320, 180)) };
+    let matrix = Mat4::box2d(vec2(100.3, 50.8), vec2(16.0, 16.0));
+    let snapped = settings.snap_matrix(&matrix);
+    let corners = Obb::from_matrix(&snapped).corners();
+    corners.iter().for_each(|corner| assert_whole(*corner));
+    assert_eq!(Obb::from_matrix(&snapped).center, vec2(100.0, 51.0));
+
+    // nothing happens without a grid
+    let free = Settings { snap: None, ..settings };
+    assert_eq!(free.snap_matrix(&matrix).data, matrix.data);
+}
+
+#[test]
+fn snapping_follows_the_projection() {
+    // four world units to a texel, like the demo's pixel camera
+    let settings = Settings {
+        projection: Mat4::ortho_bounds(0.0, 1280.0, 720.0, 0.0, 0.0, 1.0),
+        cull: true,
+        snap: Some(size(320, 180)),
+    };
+    let matrix = Mat4::box2d(vec2(401.0, 205.0), vec2(64.0, 32.0));
+    let corner = Obb::from_matrix(&settings.snap_matrix(&matrix)).corners()[0];
+    assert_whole(corner / 4.0);
+    assert!(corner.distance(vec2(369.0, 189.0)) <= 2.0f32.sqrt() * 2.0);
+
+    // with the y axis flipped by the projection
+    let flipped = Settings { projection: Mat4::ortho_bounds(0.0, 320.0, 0.0, 180.0, 0.0, 1.0), ..settings };
+    let corner = Obb::from_matrix(&flipped.snap_matrix(&Mat4::box2d(vec2(10.4, 10.4), vec2(8.0, 8.0)))).corners()[0];
+    assert_eq!(corner.map(f32::round), vec2(6.0, 6.0));
+}
@@ -0,0 +1,135 @@
+use graphics2d::geom::Rect;
+use graphics2d::mat::{vec2, Mat4, Vec2};
+use graphics2d::projection::{Policy, Projection};
+use winit::dpi::PhysicalSize;
+
+mod common;
+use common::{assert_rect_within, assert_within, clip};
+
+// window sized positions round more than common::assert_close allows
+const EPSILON: f32 = 1e-3;
+
+const POLICIES: [Policy; 5] = [Policy::Stretch, Policy::Fit, Policy::Fill, Policy::FixedWidth, Policy::FixedHeight];
+
+fn design() -> Vec2 {
+    vec2(320.0, 180.0)
+}
+
+// same aspect, wider, taller, square, and smaller than the design
+fn windows() -> [PhysicalSize<u32>; 5] {
+    [
+        PhysicalSize::new(1280, 720),
+        PhysicalSize::new(1920, 720),
+        PhysicalSize::new(720, 1280),
+        PhysicalSize::new(800, 800),
+        PhysicalSize::new(160, 90),
+    ]
+}
+
+fn window_rect(window: PhysicalSize<u32>) -> Rect {
+    Rect::new(vec2(0.0, 0.0), vec2(window.width as f32, window.height as f32))
+}
+
+#[test]
+fn visible_area_fills_clip_space() {
+    for policy in POLICIES {
+        for window in windows() {
+            let projection = Projection::new(policy, design(), window);
+            assert_within(clip(&projection.matrix, projection.visible.min), vec2(-1.0, 1.0), EPSILON);
+            assert_within(clip(&projection.matrix, projection.visible.max), vec2(1.0, -1.0), EPSILON);
+            assert!(window_rect(window).contains_rect(&projection.viewport), "{:?} {:?}", policy, window);
+        }
+    }
+}
+
+#[test]
+fn matching_aspect_ratios_agree() {
+    let window = PhysicalSize::new(1280, 720);
+    for policy in POLICIES {
+        let projection = Projection::new(policy, design(), window);
+        assert_rect_within(projection.viewport, window_rect(window), EPSILON);
+        assert_rect_within(projection.visible, Rect::new(vec2(0.0, 0.0), design()), EPSILON);
+        assert_eq!(projection.matrix.data, Mat4::ortho_bounds(0.0, 320.0, 180.0, 0.0, 0.0, 1.0).data);
+    }
+}
+
+#[test]
+fn stretch_uses_the_whole_window() {
+    for window in windows() {
+        let projection = Projection::new(Policy::Stretch, design(), window);
+        assert_rect_within(projection.viewport, window_rect(window), EPSILON);
+        assert_rect_within(projection.visible, Rect::new(vec2(0.0, 0.0), design()), EPSILON);
+    }
+}
+
+#[test]
+fn fit_letterboxes_and_keeps_the_aspect() {
+    let wide = Projection::new(Policy::Fit, design(), PhysicalSize::new(1920, 720));
+    assert_rect_within(wide.viewport, Rect::new(vec2(320.0, 0.0), vec2(1600.0, 720.0)), EPSILON);
+    let tall = Projection::new(Policy::Fit, design(), PhysicalSize::new(720, 1280));
+    assert_rect_within(tall.viewport, Rect::new(vec2(0.0, 437.0), vec2(720.0, 842.0)), EPSILON);
+    let small = Projection::new(Policy::Fit, design(), PhysicalSize::new(160, 90));
+    assert_rect_within(small.viewport, Rect::new(vec2(0.0, 0.0), vec2(160.0, 90.0)), EPSILON);
+    for window in windows() {
+        let projection = Projection::new(Policy::Fit, design(), window);
+        let size = projection.viewport.size();
+        assert!((size.x / size.y - 16.0 / 9.0).abs() < 1e-4);
+        assert_rect_within(projection.visible, Rect::new(vec2(0.0, 0.0), design()), EPSILON);
+    }
+}
+
+#[test]
+fn fill_crops_and_keeps_the_aspect() {
+    let wide = Projection::new(Policy::Fill, design(), PhysicalSize::new(1920, 720));
+    // 6x across, so 120 units of height are shown out of 180
+    assert_rect_within(wide.visible, Rect::new(vec2(0.0, 30.0), vec2(320.0, 150.0)), EPSILON);
+    let tall = Projection::new(Policy::Fill, design(), PhysicalSize::new(720, 1280));
+    assert_rect_within(tall.visible, Rect::new(vec2(109.375, 0.0), vec2(210.625, 180.0)), EPSILON);
+    for window in windows() {
+        let projection = Projection::new(Policy::Fill, design(), window);
+        assert_rect_within(projection.viewport, window_rect(window), EPSILON);
+        assert!(Rect::new(vec2(0.0, 0.0), design()).contains_rect(&projection.visible.grow(-1e-3)));
+        let visible = projection.visible.size();
+        assert!((visible.x / visible.y - window.width as f32 / window.height as f32).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn fixed_width_and_height() {
+    let tall = Projection::new(Policy::FixedWidth, design(), PhysicalSize::new(720, 1280));
+    // 1280 * 320 / 720 units of height, around the design's center
+    let half = 1280.0 * 320.0 / 720.0 / 2.0;
+    assert_rect_within(tall.visible, Rect::new(vec2(0.0, 90.0 - half), vec2(320.0, 90.0 + half)), EPSILON);
+    let wide = Projection::new(Policy::FixedHeight, design(), PhysicalSize::new(1920, 720));
+    assert_rect_within(wide.visible, Rect::new(vec2(-80.0, 0.0), vec2(400.0, 180.0)), EPSILON);
+    for window in windows() {
+        let width = Projection::new(Policy::FixedWidth, design(), window);
+        assert_within(vec2(width.visible.min.x, width.visible.max.x), vec2(0.0, 320.0), EPSILON);
+        assert_within(width.visible.center(), vec2(160.0, 90.0), EPSILON);
+        let height = Projection::new(Policy::FixedHeight, design(), window);
+        assert_within(vec2(height.visible.min.y, height.visible.max.y), vec2(0.0, 180.0), EPSILON);
+        assert_within(height.visible.center(), vec2(160.0, 90.0), EPSILON);
+        for projection in [width, height] {
+            assert_rect_within(projection.viewport, window_rect(window), EPSILON);
+            let visible = projection.visible.size();
+            assert!((visible.x / visible.y - window.width as f32 / window.height as f32).abs() < 1e-4);
+        }
+    }
+}
+
+#[test]
+fn window_and_design_positions_round_trip() {
+    for policy in POLICIES {
+        for window in windows() {
+            let projection = Projection::new(policy, design(), window);
+            let center = projection.viewport.center();
+            let design_center = projection.window_to_design(center).unwrap();
+            assert_within(design_center, projection.visible.center(), EPSILON);
+            assert_within(projection.design_to_window(design_center), center, EPSILON);
+        }
+    }
+    // over the bars
+    let wide = Projection::new(Policy::Fit, design(), PhysicalSize::new(1920, 720));
+    assert_eq!(wide.window_to_design(vec2(100.0, 360.0)), None);
+    assert_within(wide.window_to_design(vec2(320.0, 0.0)).unwrap(), vec2(0.0, 0.0), EPSILON);
+}
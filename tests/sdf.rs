@@ -0,0 +1,70 @@
+use graphics2d::text::{self, sdf::distance_field, BaseFontInfoContainer};
+
+// an 8x8 bitmap with a filled 4x4 square in the middle
+fn square() -> Vec<u8> {
+    (0..64).map(|i| if (2..6).contains(&(i % 8)) && (2..6).contains(&(i / 8)) { 255 } else { 0 }).collect()
+}
+
+#[test]
+fn distance_field_is_padded() {
+    let field = distance_field(&square(), 8, 8, 4);
+    assert_eq!(field.len(), 16 * 16);
+}
+
+#[test]
+fn distance_field_is_half_at_the_edge() {
+    let field = distance_field(&square(), 8, 8, 4);
+    let at = |x: usize, y: usize| field[(y + 4) * 16 + x + 4];
+    // pixels on either side of the left edge sit half a pixel from it
+    assert_eq!(at(1, 3), 112);
+    assert_eq!(at(2, 3), 143);
+    // the center is further inside than the edge
+    assert!(at(3, 3) > at(2, 3));
+    // and far corners are outside the spread entirely
+    assert_eq!(field[0], 0);
+}
+
+#[test]
+fn distance_field_is_symmetric() {
+    let field = distance_field(&square(), 8, 8, 4);
+    for y in 0..16 {
+        for x in 0..16 {
+            assert_eq!(field[y * 16 + x], field[y * 16 + 15 - x]);
+            assert_eq!(field[y * 16 + x], field[x * 16 + y]);
+        }
+    }
+}
+
+#[test]
+fn sdf_font_keeps_bitmap_metrics() {
+    let bytes = include_bytes!(env!("SAMPLE_FONT"));
+    let chars = text::default_characters();
+    let bitmap = text::make_font_infos(bytes, &[32.0], chars.iter(), None, "bitmap".to_string()).unwrap().remove(0);
+    let sdf = text::make_sdf_font_info(bytes, 32.0, chars.iter(), None, 4, "sdf".to_string()).unwrap();
+    assert_eq!(sdf.distance_field, Some(4.0));
+    assert_eq!(bitmap.distance_field, None);
+    assert_eq!(sdf.line_height(), bitmap.line_height());
+    for c in ['A', 'g', '~'] {
+        let (a, b) = (bitmap.get_metrics(&c).unwrap(), sdf.get_metrics(&c).unwrap());
+        assert_eq!(a.glyph_size.x, b.glyph_size.x);
+        assert_eq!(a.glyph_size.y, b.glyph_size.y);
+        assert_eq!(a.advance, b.advance);
+        assert_eq!(a.lsb, b.lsb);
+        assert_eq!(a.tsb, b.tsb);
+    }
+}
+
+#[test]
+fn sdf_font_atlas_holds_padded_glyphs() {
+    let bytes = include_bytes!(env!("SAMPLE_FONT"));
+    let font = text::make_sdf_font_info(bytes, 32.0, ['l'].iter(), None, 4, "sdf".to_string()).unwrap();
+    let metrics = font.get_metrics(&'l').unwrap();
+    let width = font.image_size[0] as usize;
+    let at = |x: f32, y: f32| font.image_buffer[y as usize * width + x as usize];
+    // the padding around the glyph is outside of it
+    assert!(at(metrics.glyph_pos.x - 4.0, metrics.glyph_pos.y - 4.0) < 128);
+    // and the middle of the stem is inside
+    let middle_x = metrics.glyph_pos.x + metrics.glyph_size.x / 2.0;
+    let middle_y = metrics.glyph_pos.y + metrics.glyph_size.y / 2.0;
+    assert!(at(middle_x, middle_y) > 128);
+}
@@ -0,0 +1,156 @@
+use graphics2d::{
+    mat::vec4,
+    text::{
+        cache::GlyphCache,
+        collection::FontCollection,
+        layout::{layout_shaped, LayoutSettings, Span},
+        shaping::{shape_spans, ShapingFont},
+    },
+};
+
+fn cache(bytes: &[u8]) -> GlyphCache {
+    GlyphCache::new(bytes, 16.0, 512, 1, "cache".to_string()).unwrap()
+}
+
+// a monospace font without Hebrew that falls back to one with it
+fn collection() -> FontCollection<GlyphCache> {
+    let mut collection = FontCollection::new();
+    let mono = collection.add(cache(include_bytes!(env!("SAMPLE_FONT"))));
+    let sans = collection.add(cache(include_bytes!(env!("SAMPLE_FALLBACK_FONT"))));
+    collection.set_fallbacks(mono, &[sans]);
+    collection
+}
+
+fn span(text: &str) -> Span<'_> {
+    Span { text, font: 0, color: vec4(1.0, 1.0, 1.0, 1.0) }
+}
+
+#[test]
+fn falls_back_to_the_first_font_with_the_char() {
+    let collection = collection();
+    assert_eq!(collection.font_for(0, 'a'), 0);
+    assert_eq!(collection.font_for(0, 'ש'), 1);
+    // nothing has it, so it stays in the requested font
+    assert_eq!(collection.font_for(0, '中'), 0);
+    // fallback chains only go one way
+    assert_eq!(collection.font_for(1, 'ש'), 1);
+    assert!(collection.fallbacks(1).is_empty());
+}
+
+#[test]
+fn splits_spans_into_runs_by_font() {
+    let collection = collection();
+    let red = vec4(1.0, 0.0, 0.0, 1.0);
+    let spans = [span("ab שלום cd"), Span { text: "ש", font: 0, color: red }];
+    let runs = collection.split(&spans);
+    let texts: Vec<(&str, usize)> = runs.iter().map(|run| (run.text, run.font)).collect();
+    // spaces stay with the run before them
+    assert_eq!(texts, vec![("ab ", 0), ("שלום ", 1), ("cd", 0), ("ש", 1)]);
+    assert_eq!(runs[3].color.x, 1.0);
+    assert_eq!(runs[3].color.y, 0.0);
+}
+
+#[test]
+fn shapes_contextual_forms() {
+    let collection = collection();
+    let shaper = collection.fonts[0].shaper();
+    let isolated = shaper.shape("ب", 16.0, true);
+    let joined = shaper.shape("بب", 16.0, true);
+    assert_eq!(joined.len(), 2);
+    assert!(joined.iter().all(|glyph| glyph.glyph != isolated[0].glyph));
+    // right to left glyphs come out in visual order
+    assert_eq!(joined.iter().map(|glyph| glyph.cluster).collect::<Vec<_>>(), vec![2, 0]);
+}
+
+#[test]
+fn composes_combining_marks() {
+    let collection = collection();
+    let shaped = collection.fonts[1].shaper().shape("e\u{301}", 16.0, false);
+    assert_eq!(shaped.len(), 1);
+    assert_eq!(shaped[0].c, 'e');
+}
+
+#[test]
+fn reorders_bidi_runs() {
+    let collection = collection();
+    let spans = collection.split(&[span("abc אבג")]);
+    let lines = shape_spans(&collection.fonts, &spans);
+    assert_eq!(lines.len(), 1);
+    let runs = &lines[0];
+    assert_eq!(runs.len(), 2);
+    assert!(!runs[0].rtl && runs[0].font == 0);
+    assert!(runs[1].rtl && runs[1].font == 1);
+    assert_eq!(runs[1].glyphs.iter().map(|glyph| glyph.c).collect::<String>(), "גבא");
+    assert_eq!(runs[1].glyphs.iter().map(|glyph| glyph.cluster).collect::<Vec<_>>(), vec![4, 2, 0]);
+}
+
+// drawn chars of each line from left to right
+fn visual_lines(collection: &mut FontCollection<GlyphCache>, text: &str, max_width: Option<f32>) -> Vec<String> {
+    let spans = collection.split(&[span(text)]);
+    let lines = shape_spans(&collection.fonts, &spans);
+    for run in lines.iter().flatten() {
+        collection.fonts[run.font].prepare_glyphs(1, run.glyphs.iter().map(|glyph| glyph.glyph));
+    }
+    let settings = LayoutSettings { max_width, ..Default::default() };
+    let result = layout_shaped(&collection.fonts, &lines, &settings);
+    result.lines.iter()
+        .map(|line| {
+            let mut glyphs = result.glyphs[line.glyphs.clone()].to_vec();
+            glyphs.sort_by(|a, b| a.position.x.total_cmp(&b.position.x));
+            glyphs.iter().map(|glyph| glyph.c).collect()
+        })
+        .collect()
+}
+
+#[test]
+fn right_to_left_paragraphs_start_on_the_right() {
+    let mut collection = collection();
+    assert_eq!(visual_lines(&mut collection, "אבג abc", None), vec!["abcגבא"]);
+}
+
+#[test]
+fn right_to_left_paragraphs_wrap_from_the_right() {
+    let mut collection = collection();
+    let text = "אבג דהו זחט";
+    let width = {
+        let spans = collection.split(&[span(text)]);
+        let lines = shape_spans(&collection.fonts, &spans);
+        lines[0].iter().flat_map(|run| &run.glyphs).map(|glyph| glyph.advance).sum::<f32>()
+    };
+    // the last word in reading order is the one that wraps, each line reads
+    // from the right
+    let lines = visual_lines(&mut collection, text, Some(width - 1.0));
+    assert_eq!(lines, vec!["והדגבא", "טחז"]);
+    let lines = visual_lines(&mut collection, "אבג דהו זחט\nכלמ", Some(width - 1.0));
+    assert_eq!(lines, vec!["והדגבא", "טחז", "מלכ"]);
+}
+
+#[test]
+fn shaped_text_feeds_the_layout() {
+    let mut collection = collection();
+    let spans = collection.split(&[span("ab\nשל")]);
+    let lines = shape_spans(&collection.fonts, &spans);
+    assert_eq!(lines.len(), 2);
+    for run in lines.iter().flatten() {
+        collection.fonts[run.font].prepare_glyphs(1, run.glyphs.iter().map(|glyph| glyph.glyph));
+    }
+    let result = layout_shaped(&collection.fonts, &lines, &LayoutSettings::default());
+    assert_eq!(result.lines.len(), 2);
+    assert_eq!(result.glyphs.len(), 4);
+    assert!(result.glyphs.iter().all(|glyph| glyph.glyph.is_some()));
+    let second: Vec<_> = result.glyphs[result.lines[1].glyphs.clone()].iter().map(|glyph| (glyph.c, glyph.font)).collect();
+    assert_eq!(second, vec![('ל', 1), ('ש', 1)]);
+    assert!(result.glyphs[0].position.x < result.glyphs[1].position.x);
+}
+
+#[test]
+fn unprepared_glyphs_still_advance() {
+    let mut collection = collection();
+    collection.fonts[0].prepare(1, "b");
+    let lines = shape_spans(&collection.fonts, &[span("a b")]);
+    let result = layout_shaped(&collection.fonts, &lines, &LayoutSettings::default());
+    // only the b is drawn, after the a and the space
+    assert_eq!(result.glyphs.len(), 1);
+    let advance: f32 = lines[0][0].glyphs[..2].iter().map(|glyph| glyph.advance).sum();
+    assert!(result.glyphs[0].position.x >= advance);
+}
@@ -0,0 +1,83 @@
+use graphics2d::win::{acquire_frame, pick_sample_count, Acquired, Swapchain};
+use wgpu::SurfaceError;
+
+// Hands out the queued results in order, then succeeds forever
+struct FakeSwapchain {
+    results: Vec<Result<u32, SurfaceError>>,
+    acquires: u32,
+    reconfigures: u32,
+}
+
+impl FakeSwapchain {
+    fn new(mut results: Vec<Result<u32, SurfaceError>>) -> Self {
+        results.reverse();
+        Self { results, acquires: 0, reconfigures: 0 }
+    }
+}
+
+impl Swapchain for FakeSwapchain {
+    type Frame = u32;
+
+    fn acquire(&mut self) -> Result<u32, SurfaceError> {
+        self.acquires += 1;
+        self.results.pop().unwrap_or(Ok(self.acquires))
+    }
+
+    fn reconfigure(&mut self) {
+        self.reconfigures += 1;
+    }
+}
+
+#[test]
+fn healthy_surface_is_not_reconfigured() {
+    let mut swapchain = FakeSwapchain::new(vec![]);
+    assert!(matches!(acquire_frame(&mut swapchain), Acquired::Frame(1)));
+    assert_eq!(swapchain.reconfigures, 0);
+}
+
+#[test]
+fn lost_surface_is_reconfigured_and_retried() {
+    let mut swapchain = FakeSwapchain::new(vec![Err(SurfaceError::Lost)]);
+    assert!(matches!(acquire_frame(&mut swapchain), Acquired::Frame(2)));
+    assert_eq!(swapchain.reconfigures, 1);
+}
+
+#[test]
+fn outdated_surface_is_reconfigured_and_retried() {
+    let mut swapchain = FakeSwapchain::new(vec![Err(SurfaceError::Outdated)]);
+    assert!(matches!(acquire_frame(&mut swapchain), Acquired::Frame(2)));
+    assert_eq!(swapchain.reconfigures, 1);
+}
+
+#[test]
+fn surface_still_lost_after_reconfigure_skips_the_frame() {
+    let mut swapchain = FakeSwapchain::new(vec![Err(SurfaceError::Lost), Err(SurfaceError::Lost)]);
+    assert!(matches!(acquire_frame(&mut swapchain), Acquired::Skip));
+    assert!(matches!(acquire_frame(&mut swapchain), Acquired::Frame(_)));
+}
+
+#[test]
+fn timeout_skips_without_reconfiguring() {
+    let mut swapchain = FakeSwapchain::new(vec![Err(SurfaceError::Timeout)]);
+    assert!(matches!(acquire_frame(&mut swapchain), Acquired::Skip));
+    assert_eq!(swapchain.reconfigures, 0);
+}
+
+#[test]
+fn out_of_memory_exits() {
+    let mut swapchain = FakeSwapchain::new(vec![Err(SurfaceError::OutOfMemory)]);
+    assert!(matches!(acquire_frame(&mut swapchain), Acquired::Exit));
+    let mut swapchain = FakeSwapchain::new(vec![Err(SurfaceError::Lost), Err(SurfaceError::OutOfMemory)]);
+    assert!(matches!(acquire_frame(&mut swapchain), Acquired::Exit));
+}
+
+#[test]
+fn sample_counts_outside_four_need_adapter_specific_features() {
+    let all = |_: u32| true;
+    assert_eq!(pick_sample_count(8, true, all), 8);
+    assert_eq!(pick_sample_count(8, false, all), 4);
+    assert_eq!(pick_sample_count(2, false, all), 1);
+    assert_eq!(pick_sample_count(1, true, all), 1);
+    assert_eq!(pick_sample_count(16, true, |count| count <= 4), 4);
+    assert_eq!(pick_sample_count(4, false, |_| false), 1);
+}
@@ -0,0 +1,89 @@
+use std::{cell::Cell, time::{Duration, Instant}};
+
+use graphics2d::timestep::{Clock, FixedTimestep};
+
+struct FakeClock {
+    now: Cell<Instant>,
+}
+
+impl FakeClock {
+    fn new() -> Self {
+        Self { now: Cell::new(Instant::now()) }
+    }
+
+    fn advance_ms(&self, ms: u64) {
+        self.now.set(self.now.get() + Duration::from_millis(ms));
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
+
+#[test]
+fn first_step_only_starts_the_clock() {
+    let clock = FakeClock::new();
+    let mut timestep = FixedTimestep::new(100.0, 5);
+    assert_eq!(timestep.step(&clock), 0);
+    assert_eq!(timestep.alpha(), 0.0);
+}
+
+#[test]
+fn ticks_at_the_fixed_rate() {
+    let clock = FakeClock::new();
+    let mut timestep = FixedTimestep::new(100.0, 5);
+    timestep.step(&clock);
+    clock.advance_ms(10);
+    assert_eq!(timestep.step(&clock), 1);
+    clock.advance_ms(30);
+    assert_eq!(timestep.step(&clock), 3);
+    assert_eq!(timestep.dt(), 0.01);
+}
+
+#[test]
+fn short_frames_accumulate_into_a_tick() {
+    let clock = FakeClock::new();
+    let mut timestep = FixedTimestep::new(100.0, 5);
+    timestep.step(&clock);
+    clock.advance_ms(4);
+    assert_eq!(timestep.step(&clock), 0);
+    assert!((timestep.alpha() - 0.4).abs() < 1e-4);
+    clock.advance_ms(4);
+    assert_eq!(timestep.step(&clock), 0);
+    assert!((timestep.alpha() - 0.8).abs() < 1e-4);
+    clock.advance_ms(4);
+    assert_eq!(timestep.step(&clock), 1);
+    assert!((timestep.alpha() - 0.2).abs() < 1e-4);
+}
+
+#[test]
+fn long_frames_are_capped_and_drop_the_backlog() {
+    let clock = FakeClock::new();
+    let mut timestep = FixedTimestep::new(100.0, 5);
+    timestep.step(&clock);
+    clock.advance_ms(1005);
+    assert_eq!(timestep.step(&clock), 5);
+    assert!((timestep.alpha() - 0.5).abs() < 1e-4);
+    clock.advance_ms(10);
+    assert_eq!(timestep.step(&clock), 1);
+}
+
+#[test]
+fn advance_without_a_clock() {
+    let mut timestep = FixedTimestep::new(50.0, 3);
+    assert_eq!(timestep.advance(Duration::from_millis(19)), 0);
+    assert_eq!(timestep.advance(Duration::from_millis(1)), 1);
+    assert_eq!(timestep.alpha(), 0.0);
+}
+
+#[test]
+fn time_until_the_next_tick() {
+    let mut timestep = FixedTimestep::new(50.0, 3);
+    assert_eq!(timestep.until_next_tick(), Duration::from_millis(20));
+    timestep.advance(Duration::from_millis(15));
+    assert_eq!(timestep.until_next_tick(), Duration::from_millis(5));
+    timestep.advance(Duration::from_millis(5));
+    assert_eq!(timestep.until_next_tick(), Duration::from_millis(20));
+}
@@ -0,0 +1,106 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use graphics2d::mat::{vec2, vec3, vec4, Mat4, Vec2, Vec3, Vec4};
+
+mod common;
+use common::assert_close;
+
+#[test]
+fn operators_are_component_wise() {
+    let a = vec2(1.0, 2.0);
+    let b = vec2(3.0, -4.0);
+    assert_eq!(a + b, vec2(4.0, -2.0));
+    assert_eq!(a - b, vec2(-2.0, 6.0));
+    assert_eq!(a * b, vec2(3.0, -8.0));
+    assert_eq!(b / a, vec2(3.0, -2.0));
+    assert_eq!(-a, vec2(-1.0, -2.0));
+    assert_eq!(vec3(1.0, 2.0, 3.0) + vec3(1.0, 1.0, 1.0), vec3(2.0, 3.0, 4.0));
+    assert_eq!(vec4(1.0, 2.0, 3.0, 4.0) - Vec4::splat(1.0), vec4(0.0, 1.0, 2.0, 3.0));
+}
+
+#[test]
+fn scalar_operators() {
+    let a = vec3(1.0, -2.0, 4.0);
+    assert_eq!(a * 2.0, vec3(2.0, -4.0, 8.0));
+    assert_eq!(2.0 * a, a * 2.0);
+    assert_eq!(a / 2.0, vec3(0.5, -1.0, 2.0));
+
+    let mut b = a;
+    b += vec3(1.0, 1.0, 1.0);
+    b -= vec3(0.0, 1.0, 0.0);
+    b *= 3.0;
+    b /= 2.0;
+    assert_eq!(b, vec3(3.0, -3.0, 7.5));
+}
+
+#[test]
+fn dot_and_cross() {
+    assert_eq!(vec2(1.0, 2.0).dot(vec2(3.0, 4.0)), 11.0);
+    assert_eq!(vec4(1.0, 2.0, 3.0, 4.0).dot(Vec4::splat(1.0)), 10.0);
+    assert_eq!(vec3(1.0, 0.0, 0.0).cross(vec3(0.0, 1.0, 0.0)), vec3(0.0, 0.0, 1.0));
+    assert_eq!(vec3(0.0, 1.0, 0.0).cross(vec3(1.0, 0.0, 0.0)), vec3(0.0, 0.0, -1.0));
+    assert_eq!(vec2(1.0, 0.0).cross(vec2(0.0, 1.0)), 1.0);
+    assert_eq!(vec2(0.0, 1.0).cross(vec2(1.0, 0.0)), -1.0);
+}
+
+#[test]
+fn length_and_normalize() {
+    let a = vec2(3.0, 4.0);
+    assert_eq!(a.length_squared(), 25.0);
+    assert_eq!(a.length(), 5.0);
+    assert_eq!(a.distance(vec2(0.0, 0.0)), 5.0);
+    assert_close(a.normalize(), vec2(0.6, 0.8));
+    assert!((vec3(1.0, 2.0, 2.0).normalize().length() - 1.0).abs() < 1e-6);
+    // no NaNs from the zero vector
+    assert_eq!(Vec3::splat(0.0).normalize(), Vec3::splat(0.0));
+}
+
+#[test]
+fn lerp_min_max() {
+    let a = vec2(0.0, 10.0);
+    let b = vec2(10.0, -10.0);
+    assert_eq!(a.lerp(b, 0.0), a);
+    assert_eq!(a.lerp(b, 1.0), b);
+    assert_eq!(a.lerp(b, 0.25), vec2(2.5, 5.0));
+    assert_eq!(a.min(b), vec2(0.0, -10.0));
+    assert_eq!(a.max(b), vec2(10.0, 10.0));
+    assert_eq!(vec2(-1.5, 2.0).abs(), vec2(1.5, 2.0));
+    assert_eq!(vec2(1.4, 1.6).map(f32::round), vec2(1.0, 2.0));
+}
+
+#[test]
+fn perp_and_angles() {
+    let a = vec2(2.0, 1.0);
+    assert_eq!(a.perp(), vec2(1.0, -2.0));
+    assert_eq!(a.perp(), a.rotate(FRAC_PI_2).map(f32::round));
+    assert_eq!(a.dot(a.perp()), 0.0);
+    // y down, so up the screen is a quarter turn
+    assert_eq!(vec2(0.0, -1.0).angle(), FRAC_PI_2);
+    assert_eq!(vec2(0.0, 1.0).angle(), -FRAC_PI_2);
+    assert_eq!(vec2(-1.0, -1e-9).angle(), PI);
+    assert!((vec2(1.0, 0.0).angle_to(vec2(0.0, -1.0)) - FRAC_PI_2).abs() < 1e-6);
+    assert!((vec2(0.0, -1.0).angle_to(vec2(1.0, 0.0)) + FRAC_PI_2).abs() < 1e-6);
+}
+
+#[test]
+fn rotate() {
+    assert_close(vec2(1.0, 0.0).rotate(FRAC_PI_2), vec2(0.0, -1.0));
+    assert_close(vec2(1.0, 2.0).rotate(PI), vec2(-1.0, -2.0));
+    assert_close(Vec2::from_angle(PI / 3.0), vec2(1.0, 0.0).rotate(PI / 3.0));
+    let a = vec2(3.0, -7.0);
+    assert!((a.rotate(1.234).length() - a.length()).abs() < 1e-5);
+    assert!((a.angle_to(a.rotate(0.5)) - 0.5).abs() < 1e-5);
+    assert!((Vec2::from_angle(0.5).angle() - 0.5).abs() < 1e-6);
+    // same direction as the matrices
+    let rotated = Mat4::rotate2d(0.5) % vec4(a.x, a.y, 0.0, 1.0);
+    assert_close(a.rotate(0.5), vec2(rotated.x, rotated.y));
+}
+
+#[test]
+fn vectors_stay_pod() {
+    let vertices = [vec2(1.0, 2.0), vec2(3.0, 4.0)];
+    let floats: &[f32] = bytemuck::cast_slice(&vertices);
+    assert_eq!(floats, &[1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(std::mem::size_of::<Vec3>(), 12);
+    assert_eq!(Vec4::from([1.0, 2.0, 3.0, 4.0]), vec4(1.0, 2.0, 3.0, 4.0));
+}